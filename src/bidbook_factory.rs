@@ -0,0 +1,143 @@
+use scrypto::prelude::*;
+
+#[derive(Debug, PartialEq, Clone, ScryptoSbor)]
+pub struct BidbookEntry {
+    pub collection: ResourceAddress,
+    pub quote_resource: ResourceAddress,
+    pub bidbook: ComponentAddress,
+}
+
+#[blueprint]
+mod bidbook_factory {
+    use crate::hello_swap::hello_swap::HelloSwap;
+    use crate::hello_swap::{OrderLimits, RoyaltyConfig};
+
+    // Only the factory owner can register a pair - otherwise anyone could claim a collection's book
+    // with fee caps and an owner badge of their choosing. The registry is the canonical list for frontends:
+    // books instantiated directly from HelloSwap still work, but are not listed here.
+
+    enable_method_auth! {
+        methods {
+            create_bidbook => restrict_to: [OWNER];
+            get_bidbook => PUBLIC;
+            get_collection_bidbooks => PUBLIC;
+            get_bidbooks => PUBLIC;
+            get_bidbook_count => PUBLIC;
+        }
+    }
+
+    struct BidbookFactory {
+        bidbooks: KeyValueStore<(ResourceAddress, ResourceAddress), Global<HelloSwap>>,
+        bidbook_index: KeyValueStore<u64, BidbookEntry>,
+        bidbook_count: u64,
+        collection_quotes: KeyValueStore<ResourceAddress, Vec<ResourceAddress>>,
+    }
+
+    impl BidbookFactory {
+        pub fn instantiate_bidbook_factory(owner_badge: ResourceAddress) -> Global<BidbookFactory> {
+            Self {
+                bidbooks: KeyValueStore::new(),
+                bidbook_index: KeyValueStore::new(),
+                bidbook_count: 0,
+                collection_quotes: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::Fixed(rule!(require(owner_badge))))
+            .globalize()
+        }
+
+        pub fn create_bidbook(
+            &mut self,
+            collection: ResourceAddress,
+            quote_resource: ResourceAddress,
+            owner_badge: Option<ResourceAddress>,
+            max_fee_bps: u32,
+            royalty_override: Option<RoyaltyConfig>,
+            order_limits: OrderLimits,
+        ) -> (Global<HelloSwap>, Option<Bucket>) {
+            assert!(
+                self.bidbooks.get(&(collection, quote_resource)).is_none(),
+                "[Create Bidbook] : A bidbook already exists for this collection and quote resource"
+            );
+
+            let (bidbook, new_owner_badge) = Blueprint::<HelloSwap>::instantiate_collection_bidbook(
+                collection,
+                quote_resource,
+                owner_badge,
+                max_fee_bps,
+                royalty_override,
+                order_limits,
+            );
+
+            // register the bidbook by pair, by insertion index for enumeration, and under its collection
+
+            self.bidbooks.insert((collection, quote_resource), bidbook);
+
+            self.bidbook_index.insert(
+                self.bidbook_count,
+                BidbookEntry {
+                    collection,
+                    quote_resource,
+                    bidbook: bidbook.address(),
+                },
+            );
+            self.bidbook_count += 1;
+
+            let collection_exists = self.collection_quotes.get(&collection).is_some();
+
+            if collection_exists {
+                let mut quotes = self.collection_quotes.get_mut(&collection).unwrap();
+                quotes.push(quote_resource);
+            } else {
+                self.collection_quotes
+                    .insert(collection, vec![quote_resource]);
+            }
+
+            (bidbook, new_owner_badge)
+        }
+
+        pub fn get_bidbook(
+            &self,
+            collection: ResourceAddress,
+            quote_resource: ResourceAddress,
+        ) -> Option<Global<HelloSwap>> {
+            self.bidbooks
+                .get(&(collection, quote_resource))
+                .map(|bidbook| bidbook.clone())
+        }
+
+        // Lookup by collection alone - one entry per quote resource the collection is traded in, in creation order
+
+        pub fn get_collection_bidbooks(&self, collection: ResourceAddress) -> Vec<BidbookEntry> {
+            let quotes = match self.collection_quotes.get(&collection) {
+                Some(quotes) => quotes.clone(),
+                None => return vec![],
+            };
+
+            quotes
+                .into_iter()
+                .map(|quote_resource| BidbookEntry {
+                    collection,
+                    quote_resource,
+                    bidbook: self
+                        .bidbooks
+                        .get(&(collection, quote_resource))
+                        .unwrap()
+                        .address(),
+                })
+                .collect()
+        }
+
+        pub fn get_bidbooks(&self, start: u64, limit: u64) -> Vec<BidbookEntry> {
+            let end = start.saturating_add(limit).min(self.bidbook_count);
+
+            (start..end)
+                .filter_map(|index| self.bidbook_index.get(&index).map(|entry| entry.clone()))
+                .collect()
+        }
+
+        pub fn get_bidbook_count(&self) -> u64 {
+            self.bidbook_count
+        }
+    }
+}
@@ -12,96 +12,414 @@ pub enum OrderStatus {
 #[derive(Debug, PartialEq, Clone, ScryptoSbor)]
 pub struct Order {
     pub order_id: NonFungibleGlobalId,
-    pub order_qty: Decimal,
+    pub order_qty: u64,
+    pub filled_qty: u64,
+    pub remaining_qty: u64,
     pub order_price: Decimal,
     pub order_total: Decimal,
     pub order_time: i64,
+    pub expires_at: Option<i64>,
     pub order_sequence: u64,
     pub order_status: OrderStatus,
+    pub maker_fee_bps: i32,
+    pub effective_price: Decimal,
 }
 
 #[derive(Debug, Clone, ScryptoSbor, NonFungibleData)]
 pub struct OrderReceipt {
-    pub order_qty: Decimal,
+    pub order_qty: u64,
     pub order_price: Decimal,
     pub order_total: Decimal,
     pub order_time: i64,
+    pub expires_at: Option<i64>,
     pub order_sequence: u64,
+    // orders already queued at the price level when this one was placed - it is not updated
+    // as orders ahead are filled or cancelled, get_queue_position gives the live position
+    pub queue_position: u64,
+    pub maker_fee_bps: i32,
+    pub effective_price: Decimal,
+    #[mutable]
     pub order_status: OrderStatus,
+    #[mutable]
+    pub filled_qty: u64,
+    #[mutable]
+    pub remaining_qty: u64,
+    #[mutable]
+    pub avg_fill_price: Decimal,
 }
 
+// Each price level is a queue of orders, kept as a doubly linked list in the level_links key value store.
+// Only the head and tail are stored on the line, so queueing, filling and cancelling an order never loads the whole level.
+// Price-time priority: the queue is kept in (order_time, order_sequence) order.
+// New orders are appended and the sequence is never reset, so the head of a level is always its oldest order.
+
 #[derive(Debug, PartialEq, Clone, ScryptoSbor)]
 pub struct OrderbookLine {
     pub level_price: Decimal,
     pub level_qty: u64,
     pub level_total: Decimal,
-    pub level_orders: Vec<NonFungibleGlobalId>,
+    pub level_head: Option<NonFungibleGlobalId>,
+    pub level_tail: Option<NonFungibleGlobalId>,
+}
+
+#[derive(Debug, PartialEq, Clone, ScryptoSbor)]
+pub struct LevelLink {
+    pub prev_order: Option<NonFungibleGlobalId>,
+    pub next_order: Option<NonFungibleGlobalId>,
+}
+
+#[derive(Debug, PartialEq, Clone, ScryptoSbor)]
+pub struct DepthLevel {
+    pub level_price: Decimal,
+    pub order_count: u64,
+    pub level_total: Decimal,
+}
+
+// Bid prices must be a multiple of the tick size, and bids must meet the minimum qty and value
+
+#[derive(Debug, PartialEq, Clone, ScryptoSbor)]
+pub struct OrderLimits {
+    pub tick_size: Decimal,
+    pub min_order_value: Decimal,
+    pub min_order_qty: u64,
+}
+
+#[derive(Debug, PartialEq, Clone, ScryptoSbor)]
+pub struct RoyaltyConfig {
+    pub royalty_percentage: Decimal,
+    pub royalty_badge: ResourceAddress,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidPlaced {
+    pub order_id: NonFungibleGlobalId,
+    pub order_price: Decimal,
+    pub order_qty: u64,
+    pub order_total: Decimal,
+    pub order_sequence: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidFilled {
+    pub order_id: NonFungibleGlobalId,
+    pub order_price: Decimal,
+    pub fill_qty: u64,
+    pub fill_value: Decimal,
+    pub order_sequence: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidPartiallyFilled {
+    pub order_id: NonFungibleGlobalId,
+    pub order_price: Decimal,
+    pub fill_qty: u64,
+    pub fill_value: Decimal,
+    pub filled_qty: u64,
+    pub remaining_qty: u64,
+    pub order_sequence: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidCancelled {
+    pub order_id: NonFungibleGlobalId,
+    pub order_price: Decimal,
+    pub remaining_qty: u64,
+    pub refunded_amount: Decimal,
+    pub order_sequence: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BidExpired {
+    pub order_id: NonFungibleGlobalId,
+    pub order_price: Decimal,
+    pub remaining_qty: u64,
+    pub order_sequence: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct MakerRebatePaid {
+    pub order_id: NonFungibleGlobalId,
+    pub rebate_due: Decimal,
+    pub rebate_paid: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct NftsClaimed {
+    pub order_id: NonFungibleGlobalId,
+    pub nft_qty: u64,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PriceLevelCreated {
+    pub level_price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct PriceLevelRemoved {
+    pub level_price: Decimal,
 }
 
 #[blueprint]
+#[events(
+    BidPlaced,
+    BidFilled,
+    BidPartiallyFilled,
+    BidCancelled,
+    BidExpired,
+    MakerRebatePaid,
+    NftsClaimed,
+    PriceLevelCreated,
+    PriceLevelRemoved
+)]
 mod hello_swap {
+    enable_method_auth! {
+        roles {
+            admin => updatable_by: [OWNER];
+        },
+        methods {
+            place_bid => PUBLIC;
+            fill_bid => PUBLIC;
+            fill_bid_exact => PUBLIC;
+            cancel_bid => PUBLIC;
+            claim => PUBLIC;
+            retire_expired_bids => PUBLIC;
+            place_ask => PUBLIC;
+            fill_ask => PUBLIC;
+            cancel_ask => PUBLIC;
+            claim_ask => PUBLIC;
+            emergency_withdraw => PUBLIC;
+            claim_royalties => PUBLIC;
+            get_depth => PUBLIC;
+            get_ask_depth => PUBLIC;
+            best_bid => PUBLIC;
+            best_ask => PUBLIC;
+            spread => PUBLIC;
+            get_order => PUBLIC;
+            get_orders_at_level => PUBLIC;
+            get_orders_by_ids => PUBLIC;
+            get_queue_position => PUBLIC;
+            pause => restrict_to: [admin, OWNER];
+            unpause => restrict_to: [admin, OWNER];
+            set_book_metadata => restrict_to: [admin, OWNER];
+            set_fee_schedule => restrict_to: [admin, OWNER];
+            set_order_limits => restrict_to: [admin, OWNER];
+            withdraw_fees => restrict_to: [OWNER];
+            lock_settings => restrict_to: [OWNER];
+            close_book => restrict_to: [OWNER];
+        }
+    }
 
     struct HelloSwap {
         price_levels: AvlTree<Decimal, ()>,
         orderbook_lines: KeyValueStore<Decimal, OrderbookLine>,
+        level_links: KeyValueStore<NonFungibleGlobalId, LevelLink>,
         orders: KeyValueStore<NonFungibleGlobalId, Order>,
         nft_vaults: KeyValueStore<NonFungibleGlobalId, Vault>,
         bid_vaults: KeyValueStore<NonFungibleGlobalId, Vault>,
         highest_bid: Decimal,
-        lowest_bid: Decimal,
-        latest_order: i64,
         sequence_number: u64,
         collection: ResourceAddress,
+        quote_resource: ResourceAddress,
+        quote_divisibility: u8,
+        order_limits: OrderLimits,
         receipt_generator: ResourceManager,
         receipt_resource_address: ResourceAddress,
+        ask_price_levels: AvlTree<Decimal, ()>,
+        ask_orderbook_lines: KeyValueStore<Decimal, OrderbookLine>,
+        ask_orders: KeyValueStore<NonFungibleGlobalId, Order>,
+        ask_nft_vaults: KeyValueStore<NonFungibleGlobalId, Vault>,
+        ask_vaults: KeyValueStore<NonFungibleGlobalId, Vault>,
+        lowest_ask: Decimal,
+        ask_receipt_generator: ResourceManager,
+        ask_receipt_resource_address: ResourceAddress,
+        owner_badge: ResourceAddress,
+        maker_fee_bps: i32,
+        taker_fee_bps: u32,
+        max_fee_bps: u32,
+        fee_vault: Vault,
+        royalty_override: Option<RoyaltyConfig>,
+        royalty_vaults: KeyValueStore<ResourceAddress, Vault>,
+        paused: bool,
+        closed: bool,
+        settings_locked: bool,
     }
 
     impl HelloSwap {
-        pub fn instantiate_collection_bidbook(collection: ResourceAddress) -> Global<HelloSwap> {
+        pub fn instantiate_collection_bidbook(
+            collection: ResourceAddress,
+            quote_resource: ResourceAddress,
+            owner_badge: Option<ResourceAddress>,
+            max_fee_bps: u32,
+            royalty_override: Option<RoyaltyConfig>,
+            order_limits: OrderLimits,
+        ) -> (Global<HelloSwap>, Option<Bucket>) {
+            assert!(
+                max_fee_bps <= 10000,
+                "[Instantiate] : Max fee can not exceed 10000 basis points"
+            );
+
+            if let Some(royalty_override) = &royalty_override {
+                assert!(
+                    royalty_override.royalty_percentage >= dec!(0)
+                        && royalty_override.royalty_percentage <= dec!(100),
+                    "[Instantiate] : Royalty percentage must be between 0 and 100"
+                );
+            }
+
+            // The book can be quoted in any fungible - its divisibility bounds the prices that can be quoted
+
+            let quote_divisibility = match ResourceManager::from(quote_resource).resource_type() {
+                ResourceType::Fungible { divisibility } => divisibility,
+                _ => panic!("[Instantiate] : Quote resource must be fungible"),
+            };
+
+            assert!(
+                order_limits.tick_size > dec!(0),
+                "[Instantiate] : Tick size must be greater than 0"
+            );
+            assert!(
+                order_limits
+                    .tick_size
+                    .checked_round(quote_divisibility, RoundingMode::ToZero)
+                    .unwrap()
+                    == order_limits.tick_size,
+                "[Instantiate] : Tick size has more decimal places than the quote resource allows"
+            );
+            assert!(
+                order_limits.min_order_value >= dec!(0),
+                "[Instantiate] : Min order value can not be negative"
+            );
+
             let (bidbook_address_reservation, bidbook_component_address) =
                 Runtime::allocate_component_address(HelloSwap::blueprint_id());
 
             let global_caller_badge_rule = rule!(require(global_caller(bidbook_component_address)));
 
-            let receipt_generator =
-                ResourceBuilder::new_ruid_non_fungible::<OrderReceipt>(OwnerRole::None)
-                    .mint_roles(mint_roles! {
-                        minter => global_caller_badge_rule.clone();
-                        minter_updater => rule!(deny_all);
-                    })
-                    .non_fungible_data_update_roles(non_fungible_data_update_roles! {
-                        non_fungible_data_updater => global_caller_badge_rule.clone();
-                        non_fungible_data_updater_updater => rule!(deny_all);
-                    })
-                    .burn_roles(burn_roles! {
-                        burner => global_caller_badge_rule.clone();
-                        burner_updater => rule!(deny_all);
-                    })
-                    .create_with_no_initial_supply();
+            // Use the caller's owner badge if one is supplied, otherwise mint a new one and hand it back
+
+            let (owner_badge, new_owner_badge) = match owner_badge {
+                Some(owner_badge) => (owner_badge, None),
+                None => {
+                    let new_owner_badge: Bucket = ResourceBuilder::new_fungible(OwnerRole::None)
+                        .divisibility(DIVISIBILITY_NONE)
+                        .metadata(metadata! {
+                            init {
+                                "name" => "Bidbook Owner Badge", locked;
+                            }
+                        })
+                        .mint_initial_supply(1)
+                        .into();
+                    (new_owner_badge.resource_address(), Some(new_owner_badge))
+                }
+            };
+
+            let owner_rule = rule!(require(owner_badge));
+
+            let receipt_generator = ResourceBuilder::new_ruid_non_fungible::<OrderReceipt>(
+                OwnerRole::Fixed(owner_rule.clone()),
+            )
+            .mint_roles(mint_roles! {
+                minter => global_caller_badge_rule.clone();
+                minter_updater => rule!(deny_all);
+            })
+            .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                non_fungible_data_updater => global_caller_badge_rule.clone();
+                non_fungible_data_updater_updater => rule!(deny_all);
+            })
+            .burn_roles(burn_roles! {
+                burner => global_caller_badge_rule.clone();
+                burner_updater => rule!(deny_all);
+            })
+            .create_with_no_initial_supply();
 
             let receipt_resource_address = receipt_generator.address();
 
-            Self {
+            let ask_receipt_generator = ResourceBuilder::new_ruid_non_fungible::<OrderReceipt>(
+                OwnerRole::Fixed(owner_rule.clone()),
+            )
+            .mint_roles(mint_roles! {
+                minter => global_caller_badge_rule.clone();
+                minter_updater => rule!(deny_all);
+            })
+            .non_fungible_data_update_roles(non_fungible_data_update_roles! {
+                non_fungible_data_updater => global_caller_badge_rule.clone();
+                non_fungible_data_updater_updater => rule!(deny_all);
+            })
+            .burn_roles(burn_roles! {
+                burner => global_caller_badge_rule.clone();
+                burner_updater => rule!(deny_all);
+            })
+            .create_with_no_initial_supply();
+
+            let ask_receipt_resource_address = ask_receipt_generator.address();
+
+            // Book metadata can only be written by the component itself, through set_book_metadata,
+            // so lock_settings freezes it for the owner and admin as well
+
+            let bidbook = Self {
                 price_levels: AvlTree::new(),
                 orderbook_lines: KeyValueStore::new(),
+                level_links: KeyValueStore::new(),
                 orders: KeyValueStore::new(),
                 nft_vaults: KeyValueStore::new(),
                 bid_vaults: KeyValueStore::new(),
                 highest_bid: dec!(0),
-                lowest_bid: dec!(0),
-                latest_order: 0,
                 sequence_number: 0,
                 collection,
+                quote_resource,
+                quote_divisibility,
+                order_limits,
                 receipt_generator,
                 receipt_resource_address,
+                ask_price_levels: AvlTree::new(),
+                ask_orderbook_lines: KeyValueStore::new(),
+                ask_orders: KeyValueStore::new(),
+                ask_nft_vaults: KeyValueStore::new(),
+                ask_vaults: KeyValueStore::new(),
+                lowest_ask: dec!(0),
+                ask_receipt_generator,
+                ask_receipt_resource_address,
+                owner_badge,
+                maker_fee_bps: 0,
+                taker_fee_bps: 0,
+                max_fee_bps,
+                fee_vault: Vault::new(quote_resource),
+                royalty_override,
+                royalty_vaults: KeyValueStore::new(),
+                paused: false,
+                closed: false,
+                settings_locked: false,
             }
             .instantiate()
-            .prepare_to_globalize(OwnerRole::None)
+            .prepare_to_globalize(OwnerRole::Fixed(owner_rule.clone()))
+            .roles(roles!(
+                admin => owner_rule.clone();
+            ))
+            .metadata(metadata! {
+                roles {
+                    metadata_setter => global_caller_badge_rule.clone();
+                    metadata_setter_updater => rule!(deny_all);
+                    metadata_locker => owner_rule.clone();
+                    metadata_locker_updater => rule!(deny_all);
+                },
+                init {
+                    "name" => "Hello Swap Bidbook", updatable;
+                    "collection" => GlobalAddress::from(collection), updatable;
+                }
+            })
             .with_address(bidbook_address_reservation)
-            .globalize()
+            .globalize();
+
+            (bidbook, new_owner_badge)
         }
 
-        pub fn place_bid(&mut self, bid: FungibleBucket, order_price: Decimal) -> Bucket {
+        pub fn place_bid(
+            &mut self,
+            mut bid: FungibleBucket,
+            qty: u64,
+            price_per_nft: Decimal,
+            expires_at: Option<i64>,
+        ) -> (Option<Bucket>, Vec<Bucket>) {
             // Checklist of all functions:
             // price_levels: AvlTree<Decimal, ()>,
             // orderbook_lines: KeyValueStore<Decimal, OrderbookLine>,
@@ -110,155 +428,104 @@ mod hello_swap {
             // bid_vaults: KeyValueStore<NonFungibleGlobalId, Vault>,
             // highest_bid: Decimal,
 
+            assert!(!self.paused, "[Place Bid] : Trading is paused");
+            assert!(qty > 0, "[Place Bid] : Order qty must be greater than 0");
             assert!(
-                order_price > dec!(0),
+                price_per_nft > dec!(0),
                 "[Place Bid] : Order price must be greater than 0"
             );
             assert!(
-                bid.amount() > dec!(0),
-                "[Place Bid] : Bid amount must be greater than 0"
+                bid.resource_address() == self.quote_resource,
+                "[Place Bid] : Bid must be in the quote resource"
             );
             assert!(
-                bid.resource_address() == XRD,
-                "[Place Bid] : Bid must be in XRD"
+                self.is_quotable_price(price_per_nft),
+                "[Place Bid] : Order price has more decimal places than the quote resource allows"
             );
-
-            // Get the XRD of the order - verify it's been submitted for the right price level and for a whole number of NFTs
-
-            let order_qty = bid
-                .amount()
-                .checked_div(order_price)
-                .unwrap()
-                .checked_round(18, RoundingMode::ToNegativeInfinity)
-                .unwrap();
-
-            let s = order_qty.to_string();
-
-            let decimal_place_check = if let Some(pos) = s.find('.') {
-                s[pos + 1..].len() as u32
-            } else {
-                0
-            };
-
             assert!(
-                decimal_place_check == 0,
-                "[Place Bid] : Bids can not be placed for a fraction of an NFT"
+                self.is_on_tick(price_per_nft),
+                "[Place Bid] : Order price must be a multiple of the tick size"
             );
-
             assert!(
-                order_qty > dec!(0),
-                "[Place Bid] : Order qty must be greater than 0"
+                qty >= self.order_limits.min_order_qty,
+                "[Place Bid] : Order qty is below the minimum order qty of this bidbook"
+            );
+            assert!(
+                self.quote_amount(qty, price_per_nft) >= self.order_limits.min_order_value,
+                "[Place Bid] : Order value is below the minimum order value of this bidbook"
             );
 
-            let funds_added_to_orderbook = bid.amount();
-
-            // Get the order time and sequence number if at the same time. If not, reset the sequence number to 0.
-
-            let order_time = Clock::current_time(TimePrecision::Minute).seconds_since_unix_epoch;
+            // Good-till-time bids carry an expiry in seconds since the unix epoch
 
-            if order_time != self.latest_order {
-                self.latest_order = order_time;
-                self.sequence_number = 0;
-            } else {
-                self.sequence_number += 1;
+            if let Some(expires_at) = expires_at {
+                assert!(
+                    Clock::current_time_is_strictly_before(
+                        Instant::new(expires_at),
+                        TimePrecision::Minute
+                    ),
+                    "[Place Bid] : Expiry must be in the future"
+                );
             }
 
-            let order_sequence_number = self.sequence_number;
-
-            // Create a new order receipt NFT for the bid
-
-            let receipt = self.receipt_generator.mint_ruid_non_fungible({
-                OrderReceipt {
-                    order_qty,
-                    order_price,
-                    order_total: bid.amount().clone(),
-                    order_time,
-                    order_sequence: order_sequence_number,
-                    order_status: OrderStatus::OPEN,
-                }
-            });
-
-            // Create a new vault for the XRD in the order
+            // The bid must cover qty * price - any excess is handed back as change.
+            // A positive maker fee is escrowed on top of the price, so it can be charged when the bid is filled.
 
-            let order_vault = Vault::with_bucket(bid.into());
+            let escrow_price = self.escrow_price(price_per_nft);
 
-            // Insert the the order_vault into the bid_vaults key value store using NFGID as the key
+            let order_value = self.quote_amount(qty, escrow_price);
 
-            let order_id = NonFungibleGlobalId::new(
-                receipt.resource_address(),
-                receipt.as_non_fungible().non_fungible_local_id(),
+            assert!(
+                bid.amount() >= order_value,
+                "[Place Bid] : Bid amount does not cover qty * price"
             );
 
-            self.bid_vaults.insert(order_id.clone(), order_vault);
-
-            // Insert a record of the order into the orders key value store using NFGID as the key
-
-            let order_id_insert = order_id.clone();
-
-            let order = Order {
-                order_id: order_id_insert,
-                order_qty,
-                order_total: funds_added_to_orderbook.clone(),
-                order_price,
-                order_time,
-                order_sequence: order_sequence_number,
-                order_status: OrderStatus::OPEN,
-            };
-
-            self.orders.insert(order_id.clone(), order.clone());
-
-            // check if price level already exists
-            // if it doesn't create new price level and orderbook line
-            // if it does, update the orderbook line at that price level
-
-            let mut price_level_exists = false;
+            // Match against resting asks priced at or below the bid, cheapest and oldest first
 
-            if self.price_levels.get(&order_price).is_some() {
-                price_level_exists = true;
-            }
+            let mut fills: Vec<Bucket> = vec![];
+            let mut remaining_qty = qty;
 
-            if !price_level_exists {
-                self.price_levels.insert(order_price, ());
+            if self.lowest_ask != dec!(0) && self.lowest_ask <= price_per_nft {
+                let (bought_nfts, bought_qty) = self.match_asks(&mut bid, qty, price_per_nft);
+                remaining_qty -= bought_qty;
+                fills.extend(bought_nfts);
             }
 
-            let orderbook_line_exists = self.orderbook_lines.get(&order_price).is_some();
+            // The limits checked above apply to the resting order as well - a remainder left below the
+            // minimum qty or value by the matched asks is not rested, its quote is returned with the fills
 
-            if orderbook_line_exists {
-                if let Some(mut orderbook_line) = self.orderbook_lines.get_mut(&order_price) {
-                    orderbook_line.level_qty += 1;
-                    orderbook_line.level_total += funds_added_to_orderbook;
-                    orderbook_line.level_orders.push(order_id.clone());
-                }
-            } else {
-                let new_orderbook_line = OrderbookLine {
-                    level_price: order_price,
-                    level_qty: 1,
-                    level_total: funds_added_to_orderbook,
-                    level_orders: vec![order_id.clone()],
-                };
+            let remainder_rests = remaining_qty >= self.order_limits.min_order_qty
+                && self.quote_amount(remaining_qty, price_per_nft)
+                    >= self.order_limits.min_order_value;
 
-                self.orderbook_lines.insert(order_price, new_orderbook_line);
+            if remaining_qty == 0 || !remainder_rests {
+                fills.push(bid.into());
+                return (None, fills);
             }
 
-            // Update the highest bid if the new bid is higher than the current highest bid
-
-            if order_price > self.highest_bid {
-                self.highest_bid = order_price;
-            }
+            // The change and any price improvement from the matched asks are returned, only the remainder rests at the bid price
 
-            // Update the lowest bid if the new bid is lower than the current lowest bid
+            let resting_value = self.quote_amount(remaining_qty, escrow_price);
 
-            if order_price < self.lowest_bid {
-                self.lowest_bid = order_price;
+            if bid.amount() > resting_value {
+                fills.push(bid.take(bid.amount() - resting_value).into());
             }
 
-            // Return the order receipt NFT
+            let receipt = self.rest_bid(bid, price_per_nft, remaining_qty, expires_at);
 
-            receipt
+            (Some(receipt), fills)
         }
 
-        pub fn fill_bid(&mut self, nfts: NonFungibleBucket) -> (Vec<Bucket>, Option<Vec<Bucket>>) {
-            let mut payment_to_seller: Vec<Bucket> = vec![];
+        // Immediate-or-cancel: returns the seller's proceeds net of the protocol fee, any unsold NFTs, and the fee taken.
+        // Matching stops before any bid priced below min_price_per_nft - the NFTs left over are returned unsold.
+        // If min_total_proceeds is given, the whole fill aborts when the net proceeds fall short of it.
+
+        pub fn fill_bid(
+            &mut self,
+            nfts: NonFungibleBucket,
+            min_price_per_nft: Decimal,
+            min_total_proceeds: Option<Decimal>,
+        ) -> (Vec<Bucket>, Option<Vec<Bucket>>, Decimal) {
+            assert!(!self.paused, "[Fill Bid] : Trading is paused");
 
             assert!(
                 nfts.non_fungible_local_ids().len() > 0,
@@ -272,178 +539,606 @@ mod hello_swap {
 
             assert!(self.highest_bid != dec!(0), "[Fill Bid] : No bids to fill");
 
-            let nft_bucket_vec_holder: Vec<Bucket> = vec![nfts.into()];
-            let mut nft_bucket_vec_holder = Some(nft_bucket_vec_holder);
-            let mut keys_to_remove = Vec::new();
+            assert!(
+                min_price_per_nft >= dec!(0),
+                "[Fill Bid] : Min price per NFT can not be negative"
+            );
 
-            for (price_level, _value, next_key) in self
-                .price_levels
-                .range_back(self.highest_bid..self.lowest_bid)
-            {
-                // get the order vector for the current price level
-                let order_vec = {
-                    let orderline = self.orderbook_lines.get(&price_level).unwrap();
-                    orderline.level_orders.clone()
-                };
+            let (proceeds, unsold_nfts, fee) = self.match_bids(nfts.into(), min_price_per_nft);
 
-                // loop through the potential_bids_to_fill vector, fill the orders - if nft amount has not been filled, then move to the next bid
-                // or if it had been filled then continue with the rest of the function
+            if let Some(min_total_proceeds) = min_total_proceeds {
+                assert!(
+                    proceeds[0].amount() >= min_total_proceeds,
+                    "[Fill Bid] : Proceeds are below the min total proceeds"
+                );
+            }
 
-                // If all NFTs have been filled, break out of the price level loop as well
-                if nft_bucket_vec_holder.is_none() {
-                    self.highest_bid = price_level;
-                    break;
-                }
+            (proceeds, unsold_nfts, fee)
+        }
 
-                for find_order in &order_vec {
-                    let mut nft_bucket_vec_holder_inner = nft_bucket_vec_holder.take().unwrap();
+        // Fill-or-kill: every NFT in the bucket is sold within the limits, or the whole transaction aborts
 
-                    let order_id = find_order.clone();
+        pub fn fill_bid_exact(
+            &mut self,
+            nfts: NonFungibleBucket,
+            min_price_per_nft: Decimal,
+            min_total_proceeds: Option<Decimal>,
+        ) -> (Vec<Bucket>, Decimal) {
+            let (proceeds, unsold_nfts, fee) =
+                self.fill_bid(nfts, min_price_per_nft, min_total_proceeds);
 
-                    let mut nfts = nft_bucket_vec_holder_inner.pop().unwrap();
+            assert!(
+                unsold_nfts.is_none(),
+                "[Fill Bid Exact] : Not all NFTs could be matched"
+            );
 
-                    let order = self.orders.get(&order_id).unwrap().clone();
+            (proceeds, fee)
+        }
 
-                    let order_qty = order.order_qty;
+        pub fn cancel_bid(&mut self, receipt: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                receipt.resource_address() == self.receipt_resource_address,
+                "[Cancel Bid] : Receipt must be an order receipt from this bidbook"
+            );
+            assert!(
+                receipt.amount() == dec!(1),
+                "[Cancel Bid] : Only one order can be cancelled at a time"
+            );
 
-                    let nft_qty = nfts.amount();
+            let order_id = NonFungibleGlobalId::new(
+                receipt.resource_address(),
+                receipt.non_fungible_local_id(),
+            );
 
-                    assert!(
-                        nft_qty > dec!(0),
-                        "[Fill Order Partial or Full] : NFT amount must be greater than 0"
-                    );
+            let order = self
+                .orders
+                .get(&order_id)
+                .map(|order| order.clone())
+                .expect("[Cancel Bid] : Order is not open");
 
-                    match nft_qty {
-                        qty if qty == order_qty => {
-                            let mut bid_value: Vec<Bucket> = vec![];
-                            {
-                                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
-                                bid_value.push(vault.take_all());
-                            }
-                            let nft_order_fill = nfts.take(order_qty);
+            // Refund the quote resource left in the order vault, plus any NFTs already delivered by a partial fill
 
-                            // place nfts into filled orders vault
-                            let filled_order_vault = Vault::with_bucket(nft_order_fill.into());
-                            {
-                                self.nft_vaults.insert(order_id.clone(), filled_order_vault);
-                            }
+            let mut refund: Vec<Bucket> = vec![];
+            {
+                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
+                refund.push(vault.take_all());
+            }
 
-                            // update nft receipt order status to filled
-                            {
-                                let mut order = self.orders.get_mut(&order_id).unwrap();
-                                order.order_status = OrderStatus::FILLED;
-                            }
+            let refunded_amount = refund[0].amount();
 
-                            // remove order from bids key value store
-                            {
-                                self.orders.remove(&order_id);
-                            }
+            let remaining_value = self.quote_amount(order.remaining_qty, order.order_price);
 
-                            // remove the order_id from the vector of nonfungibleglobalids in the orderbook line
-                            {
-                                let mut orderline =
-                                    self.orderbook_lines.get_mut(&price_level).unwrap();
-                                orderline.level_orders.retain(|x| x != &order_id);
-                            }
+            let nfts_claimed = match self.nft_vaults.get_mut(&order_id) {
+                Some(mut vault) if !vault.is_empty() => Some(vault.take_all()),
+                _ => None,
+            };
 
-                            payment_to_seller.extend(bid_value);
-                            nft_bucket_vec_holder = None;
+            if let Some(nfts_claimed) = nfts_claimed {
+                Runtime::emit_event(NftsClaimed {
+                    order_id: order_id.clone(),
+                    nft_qty: Self::nft_count(&nfts_claimed),
+                });
+                refund.push(nfts_claimed);
+            }
 
-                            break;
-                        }
-                        qty if qty > order_qty => {
-                            let mut bid_value: Vec<Bucket> = vec![];
-                            {
-                                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
-                                bid_value.push(vault.take_all());
-                            }
-                            let nft_order_fill = nfts.take(order_qty);
+            // remove the order and take it out of its price level
 
-                            // place nfts into filled orders vault
-                            let filled_order_vault = Vault::with_bucket(nft_order_fill.into());
-                            {
-                                self.nft_vaults.insert(order_id.clone(), filled_order_vault);
-                            }
+            self.remove_order(order_id.clone());
 
-                            // update nft receipt order status to filled
-                            {
-                                let mut order = self.orders.get_mut(&order_id).unwrap();
-                                order.order_status = OrderStatus::FILLED;
-                            }
+            self.remove_from_price_level(order.order_price, &order_id, remaining_value);
 
-                            // remove order from bids key value store
-                            {
-                                self.orders.remove(&order_id);
-                            }
+            Runtime::emit_event(BidCancelled {
+                order_id,
+                order_price: order.order_price,
+                remaining_qty: order.remaining_qty,
+                refunded_amount,
+                order_sequence: order.order_sequence,
+            });
 
-                            // remove the order_id from the vector of nonfungibleglobalids in the orderbook line
-                            {
-                                let mut orderline =
-                                    self.orderbook_lines.get_mut(&price_level).unwrap();
-                                orderline.level_orders.retain(|x| x != &order_id);
-                            }
+            receipt.burn();
 
-                            // take the payment
-                            payment_to_seller.extend(bid_value);
+            refund
+        }
 
-                            // return left over nfts
-                            nft_bucket_vec_holder = Some(vec![nfts]);
-                        }
-                        qty if (qty < order_qty) && (qty > dec!(0)) => {
-                            let mut bid_value: Vec<Bucket> = vec![];
-
-                            {
-                                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
-                                let order_amount_filled = order_qty - nft_qty;
-                                let bid_value_to_take =
-                                    order_amount_filled.checked_mul(order.order_price).unwrap();
-                                bid_value.push(vault.take(bid_value_to_take));
-                            }
+        pub fn claim(&mut self, mut receipts: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                receipts.resource_address() == self.receipt_resource_address,
+                "[Claim] : Receipts must be order receipts from this bidbook"
+            );
+            assert!(
+                receipts.amount() > dec!(0),
+                "[Claim] : At least one receipt must be presented"
+            );
 
-                            let nft_order_fill = nfts.take(nft_qty);
+            let mut claimed: Vec<Bucket> = vec![];
 
-                            // place nfts into filled orders vault
-                            let filled_order_vault = Vault::with_bucket(nft_order_fill.into());
-                            {
-                                self.nft_vaults.insert(order_id.clone(), filled_order_vault);
-                            }
+            for local_id in receipts.non_fungible_local_ids() {
+                let order_id =
+                    NonFungibleGlobalId::new(self.receipt_resource_address, local_id.clone());
 
-                            // update order status to partial
-                            {
-                                let mut order = self.orders.get_mut(&order_id).unwrap();
-                                order.order_status = OrderStatus::PARTIAL;
-                            }
-                            payment_to_seller.extend(bid_value);
-                            nft_bucket_vec_holder = None;
-                            keys_to_remove.push(price_level);
-                            break;
+                // take all NFTs delivered to the order so far
+
+                let nfts_claimed = match self.nft_vaults.get_mut(&order_id) {
+                    Some(mut vault) if !vault.is_empty() => Some(vault.take_all()),
+                    _ => None,
+                };
+
+                if let Some(nfts_claimed) = nfts_claimed {
+                    Runtime::emit_event(NftsClaimed {
+                        order_id: order_id.clone(),
+                        nft_qty: Self::nft_count(&nfts_claimed),
+                    });
+                    claimed.push(nfts_claimed);
+                }
+
+                // a filled order is no longer in the orders key value store - release any quote dust and burn the receipt
+
+                let order_settled = self.orders.get(&order_id).is_none();
+
+                if order_settled {
+                    {
+                        let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
+                        if !vault.is_empty() {
+                            claimed.push(vault.take_all());
                         }
-                        _ => panic!("Should not reach here"),
                     }
-                }
 
-                if next_key.is_none() {
-                    self.highest_bid = dec!(0);
-                    self.lowest_bid = dec!(0);
-                    break;
+                    let settled_receipt = receipts.take_non_fungible(&local_id);
+                    settled_receipt.burn();
                 }
             }
-            for key in keys_to_remove {
-                self.price_levels.remove(&key);
-                self.orderbook_lines.remove(&key);
+
+            // hand back the receipts of orders that are still open
+
+            if !receipts.is_empty() {
+                claimed.push(receipts.into());
+            } else {
+                receipts.drop_empty();
             }
-            (payment_to_seller, nft_bucket_vec_holder)
+
+            claimed
         }
 
-        fn get_order(&self, order_id: NonFungibleGlobalId) -> Order {
-            self.orders.get(&order_id).unwrap().clone()
+        pub fn place_ask(
+            &mut self,
+            nfts: NonFungibleBucket,
+            price_per_nft: Decimal,
+        ) -> (Option<Bucket>, Vec<Bucket>) {
+            assert!(!self.paused, "[Place Ask] : Trading is paused");
+            assert!(
+                price_per_nft > dec!(0),
+                "[Place Ask] : Ask price must be greater than 0"
+            );
+            assert!(
+                nfts.amount() > dec!(0),
+                "[Place Ask] : NFT amount must be greater than 0"
+            );
+            assert!(
+                nfts.resource_address() == self.collection,
+                "[Place Ask] : NFTs must be from the same collection"
+            );
+            assert!(
+                self.is_quotable_price(price_per_nft),
+                "[Place Ask] : Ask price has more decimal places than the quote resource allows"
+            );
+
+            // Match against resting bids priced at or above the ask, highest and oldest first
+
+            if self.highest_bid != dec!(0) && self.highest_bid >= price_per_nft {
+                let (proceeds, unsold_nfts, _fee) = self.match_bids(nfts.into(), price_per_nft);
+
+                match unsold_nfts {
+                    Some(mut unsold_nfts) => {
+                        let nfts = unsold_nfts.pop().unwrap().as_non_fungible();
+                        let receipt = self.rest_ask(nfts, price_per_nft);
+                        (Some(receipt), proceeds)
+                    }
+                    None => (None, proceeds),
+                }
+            } else {
+                let receipt = self.rest_ask(nfts, price_per_nft);
+                (Some(receipt), vec![])
+            }
         }
 
-        fn remove_order(&mut self, order_id: NonFungibleGlobalId) {
-            self.orders.remove(&order_id);
+        pub fn fill_ask(
+            &mut self,
+            mut payment: FungibleBucket,
+            max_qty: u64,
+        ) -> (Vec<Bucket>, Bucket) {
+            assert!(!self.paused, "[Fill Ask] : Trading is paused");
+            assert!(
+                payment.resource_address() == self.quote_resource,
+                "[Fill Ask] : Payment must be in the quote resource"
+            );
+            assert!(
+                payment.amount() > dec!(0),
+                "[Fill Ask] : Payment amount must be greater than 0"
+            );
+            assert!(max_qty > 0, "[Fill Ask] : Max qty must be greater than 0");
+            assert!(self.lowest_ask != dec!(0), "[Fill Ask] : No asks to fill");
+
+            let (nfts_to_buyer, _bought_qty) = self.match_asks(&mut payment, max_qty, Decimal::MAX);
+
+            (nfts_to_buyer, payment.into())
         }
 
-        // fn remove_bid_ref(&mut self, price: Decimal) {
+        pub fn cancel_ask(&mut self, receipt: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                receipt.resource_address() == self.ask_receipt_resource_address,
+                "[Cancel Ask] : Receipt must be an ask receipt from this bidbook"
+            );
+            assert!(
+                receipt.amount() == dec!(1),
+                "[Cancel Ask] : Only one order can be cancelled at a time"
+            );
+
+            let order_id = NonFungibleGlobalId::new(
+                receipt.resource_address(),
+                receipt.non_fungible_local_id(),
+            );
+
+            let order = self
+                .ask_orders
+                .get(&order_id)
+                .map(|order| order.clone())
+                .expect("[Cancel Ask] : Order is not open");
+
+            // Return the unsold NFTs, plus any quote resource already collected from partial fills
+
+            let mut refund: Vec<Bucket> = vec![];
+            {
+                let mut vault = self.ask_nft_vaults.get_mut(&order_id).unwrap();
+                refund.push(vault.take_all());
+            }
+            {
+                let mut vault = self.ask_vaults.get_mut(&order_id).unwrap();
+                if !vault.is_empty() {
+                    refund.push(vault.take_all());
+                }
+            }
+
+            self.ask_orders.remove(&order_id);
+
+            let remaining_value = self.quote_amount(order.remaining_qty, order.order_price);
+
+            self.remove_from_ask_price_level(order.order_price, &order_id, remaining_value);
+
+            receipt.burn();
+
+            refund
+        }
+
+        pub fn claim_ask(&mut self, mut receipts: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                receipts.resource_address() == self.ask_receipt_resource_address,
+                "[Claim Ask] : Receipts must be ask receipts from this bidbook"
+            );
+            assert!(
+                receipts.amount() > dec!(0),
+                "[Claim Ask] : At least one receipt must be presented"
+            );
+
+            let mut claimed: Vec<Bucket> = vec![];
+
+            for local_id in receipts.non_fungible_local_ids() {
+                let order_id =
+                    NonFungibleGlobalId::new(self.ask_receipt_resource_address, local_id.clone());
+
+                // take all quote resource paid for the NFTs sold so far
+
+                {
+                    let mut vault = self.ask_vaults.get_mut(&order_id).unwrap();
+                    if !vault.is_empty() {
+                        claimed.push(vault.take_all());
+                    }
+                }
+
+                // a sold out ask is no longer in the ask orders key value store - burn the receipt
+
+                if self.ask_orders.get(&order_id).is_none() {
+                    let settled_receipt = receipts.take_non_fungible(&local_id);
+                    settled_receipt.burn();
+                }
+            }
+
+            // hand back the receipts of asks that are still listed
+
+            if !receipts.is_empty() {
+                claimed.push(receipts.into());
+            } else {
+                receipts.drop_empty();
+            }
+
+            claimed
+        }
+
+        // Anyone can take expired bids off the book, so depth and the best bid stop quoting them
+        // without waiting for a fill_bid to walk past. Ids that are not open bids or have not expired are skipped.
+
+        pub fn retire_expired_bids(&mut self, order_ids: Vec<NonFungibleGlobalId>) {
+            for order_id in order_ids {
+                if self.orders.get(&order_id).is_some() && self.is_expired(&order_id) {
+                    self.retire_expired_bid(order_id);
+                }
+            }
+        }
+
+        // Read-only views of the book, meant for preview transactions
+
+        pub fn get_depth(&self, levels: u32) -> Vec<DepthLevel> {
+            self.price_levels
+                .range_back(Decimal::MIN..Decimal::MAX)
+                .take(levels as usize)
+                .map(|(price_level, _value, _next_key)| {
+                    Self::depth_level(&self.orderbook_lines.get(&price_level).unwrap())
+                })
+                .collect()
+        }
+
+        pub fn get_ask_depth(&self, levels: u32) -> Vec<DepthLevel> {
+            self.ask_price_levels
+                .range(Decimal::MIN..Decimal::MAX)
+                .take(levels as usize)
+                .map(|(price_level, _value, _next_key)| {
+                    Self::depth_level(&self.ask_orderbook_lines.get(&price_level).unwrap())
+                })
+                .collect()
+        }
+
+        pub fn best_bid(&self) -> Option<Decimal> {
+            if self.highest_bid == dec!(0) {
+                None
+            } else {
+                Some(self.highest_bid)
+            }
+        }
+
+        pub fn best_ask(&self) -> Option<Decimal> {
+            if self.lowest_ask == dec!(0) {
+                None
+            } else {
+                Some(self.lowest_ask)
+            }
+        }
+
+        pub fn spread(&self) -> Option<Decimal> {
+            match (self.best_bid(), self.best_ask()) {
+                (Some(best_bid), Some(best_ask)) => Some(best_ask - best_bid),
+                _ => None,
+            }
+        }
+
+        // Looks up an open bid or ask - filled and cancelled orders are no longer stored
+
+        pub fn get_order(&self, order_id: NonFungibleGlobalId) -> Option<Order> {
+            if order_id.resource_address() == self.ask_receipt_resource_address {
+                self.ask_orders.get(&order_id).map(|order| order.clone())
+            } else {
+                self.orders.get(&order_id).map(|order| order.clone())
+            }
+        }
+
+        // Pages through the bids at a price level in queue order, starting after the cursor order
+
+        pub fn get_orders_at_level(
+            &self,
+            price: Decimal,
+            cursor: Option<NonFungibleGlobalId>,
+            limit: u32,
+        ) -> Vec<Order> {
+            let mut next_order = match cursor {
+                Some(cursor) => {
+                    assert!(
+                        self.orders
+                            .get(&cursor)
+                            .map(|order| order.order_price == price)
+                            .unwrap_or(false),
+                        "[Get Orders At Level] : Cursor is not an order at this price level"
+                    );
+                    self.level_links.get(&cursor).unwrap().next_order.clone()
+                }
+                None => match self.orderbook_lines.get(&price) {
+                    Some(orderline) => orderline.level_head.clone(),
+                    None => return vec![],
+                },
+            };
+
+            let mut orders: Vec<Order> = vec![];
+
+            while let Some(order_id) = next_order {
+                if orders.len() >= limit as usize {
+                    break;
+                }
+                orders.push(self.orders.get(&order_id).unwrap().clone());
+                next_order = self.level_links.get(&order_id).unwrap().next_order.clone();
+            }
+
+            orders
+        }
+
+        // Number of orders ahead of an open bid or ask at its price level - 0 is next in line to be filled
+
+        pub fn get_queue_position(&self, order_id: NonFungibleGlobalId) -> Option<u64> {
+            self.get_order(order_id.clone())?;
+
+            let mut position: u64 = 0;
+            let mut prev_order = self.level_links.get(&order_id)?.prev_order.clone();
+
+            while let Some(order_id) = prev_order {
+                position += 1;
+                prev_order = self.level_links.get(&order_id).unwrap().prev_order.clone();
+            }
+
+            Some(position)
+        }
+
+        // One entry per id, in the order asked for - None for ids that are not open orders of this book
+
+        pub fn get_orders_by_ids(&self, order_ids: Vec<NonFungibleGlobalId>) -> Vec<Option<Order>> {
+            order_ids
+                .into_iter()
+                .map(|order_id| self.get_order(order_id))
+                .collect()
+        }
+
+        // Royalties are held per badge, recorded when they accrue. Changing or removing the collection's
+        // royalty metadata later never strands or redirects what was already collected.
+
+        pub fn claim_royalties(&mut self, royalty_badge: Proof) -> Bucket {
+            let royalty_badge_address = royalty_badge.resource_address();
+
+            let royalty_badge = royalty_badge.check(royalty_badge_address);
+
+            assert!(
+                royalty_badge.amount() > dec!(0),
+                "[Claim Royalties] : Royalty badge proof must not be empty"
+            );
+
+            let mut vault = self
+                .royalty_vaults
+                .get_mut(&royalty_badge_address)
+                .expect("[Claim Royalties] : No royalties have accrued to this badge");
+
+            vault.take_all()
+        }
+
+        // Emergency exit for receipt holders while the book is paused or closed.
+        // Every receipt is settled in one call: open orders are cancelled and refunded,
+        // filled orders hand over their NFTs or proceeds. Bid and ask receipts are both accepted.
+
+        pub fn emergency_withdraw(&mut self, mut receipts: NonFungibleBucket) -> Vec<Bucket> {
+            assert!(
+                self.paused,
+                "[Emergency Withdraw] : Only available while the book is paused or closed"
+            );
+            assert!(
+                receipts.resource_address() == self.receipt_resource_address
+                    || receipts.resource_address() == self.ask_receipt_resource_address,
+                "[Emergency Withdraw] : Receipts must be order receipts from this bidbook"
+            );
+
+            let mut withdrawn: Vec<Bucket> = vec![];
+
+            for local_id in receipts.non_fungible_local_ids() {
+                let order_id =
+                    NonFungibleGlobalId::new(receipts.resource_address(), local_id.clone());
+                let receipt = receipts.take_non_fungible(&local_id);
+
+                if order_id.resource_address() == self.receipt_resource_address {
+                    if self.orders.get(&order_id).is_some() {
+                        withdrawn.extend(self.cancel_bid(receipt));
+                    } else {
+                        withdrawn.extend(self.claim(receipt));
+                    }
+                } else if self.ask_orders.get(&order_id).is_some() {
+                    withdrawn.extend(self.cancel_ask(receipt));
+                } else {
+                    withdrawn.extend(self.claim_ask(receipt));
+                }
+            }
+
+            receipts.drop_empty();
+
+            withdrawn
+        }
+
+        // Pausing blocks new orders and fills. Cancelling and claiming stay open so users can always reach their funds.
+
+        pub fn pause(&mut self) {
+            self.paused = true;
+        }
+
+        pub fn unpause(&mut self) {
+            assert!(!self.closed, "[Unpause] : The book is permanently closed");
+            self.paused = false;
+        }
+
+        // Wind-down mode: the book is paused for good and can never trade again.
+        // Receipt holders exit through cancel_bid, claim, cancel_ask, claim_ask or emergency_withdraw.
+
+        pub fn close_book(&mut self) {
+            self.paused = true;
+            self.closed = true;
+        }
+
+        pub fn set_book_metadata(&mut self, key: String, value: String) {
+            assert!(
+                !self.settings_locked,
+                "[Set Book Metadata] : Settings are locked"
+            );
+
+            Runtime::global_component().set_metadata(key, value);
+        }
+
+        // Maker fees are charged to bid placers when their bid is filled - a negative maker fee is a rebate paid from the fee vault.
+        // Taker fees are charged to sellers on the proceeds of every sale, through fill_bid or a filled ask.
+        // Resting bids keep the maker fee they were placed with.
+
+        pub fn set_fee_schedule(&mut self, maker_fee_bps: i32, taker_fee_bps: u32) {
+            assert!(
+                !self.settings_locked,
+                "[Set Fee Schedule] : Settings are locked"
+            );
+            assert!(
+                maker_fee_bps.unsigned_abs() <= self.max_fee_bps,
+                "[Set Fee Schedule] : Maker fee can not exceed the max fee of this bidbook"
+            );
+            assert!(
+                taker_fee_bps <= self.max_fee_bps,
+                "[Set Fee Schedule] : Taker fee can not exceed the max fee of this bidbook"
+            );
+
+            self.maker_fee_bps = maker_fee_bps;
+            self.taker_fee_bps = taker_fee_bps;
+        }
+
+        // Only applies to new bids - resting bids placed under earlier limits are left as they are
+
+        pub fn set_order_limits(&mut self, order_limits: OrderLimits) {
+            assert!(
+                !self.settings_locked,
+                "[Set Order Limits] : Settings are locked"
+            );
+            assert!(
+                order_limits.tick_size > dec!(0),
+                "[Set Order Limits] : Tick size must be greater than 0"
+            );
+            assert!(
+                self.is_quotable_price(order_limits.tick_size),
+                "[Set Order Limits] : Tick size has more decimal places than the quote resource allows"
+            );
+            assert!(
+                order_limits.min_order_value >= dec!(0),
+                "[Set Order Limits] : Min order value can not be negative"
+            );
+
+            self.order_limits = order_limits;
+        }
+
+        pub fn withdraw_fees(&mut self) -> Bucket {
+            self.fee_vault.take_all()
+        }
+
+        // Locking freezes the book's metadata, fee parameters and order limits for good - pausing stays available as a circuit breaker
+
+        pub fn lock_settings(&mut self) {
+            self.settings_locked = true;
+        }
+
+        fn depth_level(orderbook_line: &OrderbookLine) -> DepthLevel {
+            DepthLevel {
+                level_price: orderbook_line.level_price,
+                order_count: orderbook_line.level_qty,
+                level_total: orderbook_line.level_total,
+            }
+        }
+
+        fn remove_order(&mut self, order_id: NonFungibleGlobalId) {
+            self.orders.remove(&order_id);
+        }
+
+        // fn remove_bid_ref(&mut self, price: Decimal) {
         //     let bid_ref = self.bids_ref.get(&price).unwrap();
         //     assert!(
         //         bid_ref.clone().len() == 0,
@@ -497,91 +1192,935 @@ mod hello_swap {
         //     self.filled_orders.get(&order_id).unwrap()
         // }
 
-        fn fill_order_partial_or_full(
+        fn rest_bid(
             &mut self,
-            order_id: NonFungibleGlobalId,
-            mut seller_nfts: Vec<Bucket>,
-        ) -> (Option<Bucket>, Vec<Bucket>) {
-            let mut nfts = seller_nfts.pop().unwrap();
+            bid: FungibleBucket,
+            order_price: Decimal,
+            order_qty: u64,
+            expires_at: Option<i64>,
+        ) -> Bucket {
+            let funds_added_to_orderbook = bid.amount();
 
-            let order = self.orders.get(&order_id).unwrap().clone();
+            // Get the order time and the next sequence number of the book
 
-            let order_qty = order.order_qty;
+            let (order_time, order_sequence_number) = self.next_order_time_and_sequence();
 
-            let nft_qty = nfts.amount();
+            // Count the orders already queued at the price level, before this one is linked to it
 
-            assert!(
-                nft_qty > dec!(0),
-                "[Fill Order Partial or Full] : NFT amount must be greater than 0"
+            let queue_position = self
+                .orderbook_lines
+                .get(&order_price)
+                .map_or(0, |orderbook_line| orderbook_line.level_qty);
+
+            // Create a new order receipt NFT for the bid
+
+            let receipt = self.receipt_generator.mint_ruid_non_fungible({
+                OrderReceipt {
+                    order_qty,
+                    order_price,
+                    order_total: bid.amount().clone(),
+                    order_time,
+                    expires_at,
+                    order_sequence: order_sequence_number,
+                    queue_position,
+                    maker_fee_bps: self.maker_fee_bps,
+                    effective_price: self.effective_price(order_price, self.maker_fee_bps),
+                    order_status: OrderStatus::OPEN,
+                    filled_qty: 0,
+                    remaining_qty: order_qty,
+                    avg_fill_price: dec!(0),
+                }
+            });
+
+            // Create a new vault for the quote resource in the order
+
+            let order_vault = Vault::with_bucket(bid.into());
+
+            // Insert the the order_vault into the bid_vaults key value store using NFGID as the key
+
+            let order_id = NonFungibleGlobalId::new(
+                receipt.resource_address(),
+                receipt.as_non_fungible().non_fungible_local_id(),
             );
 
-            match nft_qty {
-                qty if qty > order_qty => {
-                    let mut bid_value: Vec<Bucket> = vec![];
-                    {
-                        let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
-                        bid_value.push(vault.take_all());
-                    }
-                    let nft_order_fill = nfts.take(order_qty);
+            self.bid_vaults.insert(order_id.clone(), order_vault);
 
-                    // place nfts into filled orders vault
-                    let filled_order_vault = Vault::with_bucket(nft_order_fill.into());
-                    {
-                        self.nft_vaults.insert(order_id.clone(), filled_order_vault);
-                    }
+            // Insert a record of the order into the orders key value store using NFGID as the key
 
-                    // update nft receipt order status to filled
-                    {
-                        self.update_order_to_filled(order_id.clone());
-                    }
+            let order_id_insert = order_id.clone();
 
-                    // remove order from bids key value store
-                    {
-                        self.remove_order(order_id.clone());
-                    }
+            let order = Order {
+                order_id: order_id_insert,
+                order_qty,
+                filled_qty: 0,
+                remaining_qty: order_qty,
+                order_total: funds_added_to_orderbook.clone(),
+                order_price,
+                order_time,
+                expires_at,
+                order_sequence: order_sequence_number,
+                order_status: OrderStatus::OPEN,
+                maker_fee_bps: self.maker_fee_bps,
+                effective_price: self.effective_price(order_price, self.maker_fee_bps),
+            };
 
-                    (Some(nfts), bid_value)
-                }
-                qty if (qty < order_qty) && (qty > dec!(0)) => {
-                    let mut bid_value: Vec<Bucket> = vec![];
+            self.orders.insert(order_id.clone(), order.clone());
 
-                    {
-                        let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
-                        let order_amount_filled = order_qty - nft_qty;
-                        let bid_value_to_take =
-                            order_amount_filled.checked_mul(order.order_price).unwrap();
-                        bid_value.push(vault.take(bid_value_to_take));
-                    }
+            Runtime::emit_event(BidPlaced {
+                order_id: order_id.clone(),
+                order_price,
+                order_qty,
+                order_total: funds_added_to_orderbook,
+                order_sequence: order_sequence_number,
+            });
 
-                    let nft_order_fill = nfts.take(nft_qty);
+            // the level total counts the bid at its order price, without the escrowed maker fee
 
-                    // place nfts into filled orders vault
-                    let filled_order_vault = Vault::with_bucket(nft_order_fill.into());
-                    {
-                        self.nft_vaults.insert(order_id.clone(), filled_order_vault);
-                    }
+            let listed_value = self.quote_amount(order_qty, order_price);
 
-                    // update order status to partial
-                    {
-                        self.update_order_to_partial(order_id.clone());
-                    }
+            // check if price level already exists
+            // if it doesn't create new price level and orderbook line
+            // if it does, update the orderbook line at that price level
 
-                    (None, bid_value)
-                }
-                _ => {
-                    return (None, vec![]);
-                }
+            let mut price_level_exists = false;
+
+            if self.price_levels.get(&order_price).is_some() {
+                price_level_exists = true;
             }
-        }
 
-        fn update_order_to_filled(&mut self, order_id: NonFungibleGlobalId) {
-            let mut order = self.orders.get_mut(&order_id).unwrap();
-            order.order_status = OrderStatus::FILLED;
-        }
+            if !price_level_exists {
+                self.price_levels.insert(order_price, ());
+                Runtime::emit_event(PriceLevelCreated {
+                    level_price: order_price,
+                });
+            }
+
+            let orderbook_line_exists = self.orderbook_lines.get(&order_price).is_some();
+
+            if orderbook_line_exists {
+                if let Some(mut orderbook_line) = self.orderbook_lines.get_mut(&order_price) {
+                    orderbook_line.level_qty += 1;
+                    orderbook_line.level_total += listed_value;
+                    Self::link_level_order(&mut self.level_links, &mut orderbook_line, &order_id);
+                }
+            } else {
+                let mut new_orderbook_line = OrderbookLine {
+                    level_price: order_price,
+                    level_qty: 1,
+                    level_total: listed_value,
+                    level_head: None,
+                    level_tail: None,
+                };
 
-        fn update_order_to_partial(&mut self, order_id: NonFungibleGlobalId) {
-            let mut order = self.orders.get_mut(&order_id).unwrap();
-            order.order_status = OrderStatus::PARTIAL;
+                Self::link_level_order(&mut self.level_links, &mut new_orderbook_line, &order_id);
+
+                self.orderbook_lines.insert(order_price, new_orderbook_line);
+            }
+
+            // Update the highest bid from the price levels
+
+            self.update_highest_bid();
+
+            // Return the order receipt NFT
+
+            receipt
+        }
+
+        fn rest_ask(&mut self, nfts: NonFungibleBucket, price_per_nft: Decimal) -> Bucket {
+            let order_qty = nfts.non_fungible_local_ids().len() as u64;
+
+            let order_total = self.quote_amount(order_qty, price_per_nft);
+
+            let (order_time, order_sequence_number) = self.next_order_time_and_sequence();
+
+            let queue_position = self
+                .ask_orderbook_lines
+                .get(&price_per_nft)
+                .map_or(0, |orderbook_line| orderbook_line.level_qty);
+
+            // Create a new order receipt NFT for the ask
+
+            let receipt = self.ask_receipt_generator.mint_ruid_non_fungible({
+                OrderReceipt {
+                    order_qty,
+                    order_price: price_per_nft,
+                    order_total,
+                    order_time,
+                    expires_at: None,
+                    order_sequence: order_sequence_number,
+                    queue_position,
+                    maker_fee_bps: 0,
+                    effective_price: price_per_nft,
+                    order_status: OrderStatus::OPEN,
+                    filled_qty: 0,
+                    remaining_qty: order_qty,
+                    avg_fill_price: dec!(0),
+                }
+            });
+
+            let order_id = NonFungibleGlobalId::new(
+                receipt.resource_address(),
+                receipt.as_non_fungible().non_fungible_local_id(),
+            );
+
+            // The listed NFTs are held in one vault and the quote resource paid for them is collected in another
+
+            self.ask_nft_vaults
+                .insert(order_id.clone(), Vault::with_bucket(nfts.into()));
+            self.ask_vaults
+                .insert(order_id.clone(), Vault::new(self.quote_resource));
+
+            let order = Order {
+                order_id: order_id.clone(),
+                order_qty,
+                filled_qty: 0,
+                remaining_qty: order_qty,
+                order_total,
+                order_price: price_per_nft,
+                order_time,
+                expires_at: None,
+                order_sequence: order_sequence_number,
+                order_status: OrderStatus::OPEN,
+                maker_fee_bps: 0,
+                effective_price: price_per_nft,
+            };
+
+            self.ask_orders.insert(order_id.clone(), order);
+
+            // create the ask price level and orderbook line if they do not exist yet
+
+            if self.ask_price_levels.get(&price_per_nft).is_none() {
+                self.ask_price_levels.insert(price_per_nft, ());
+            }
+
+            let orderbook_line_exists = self.ask_orderbook_lines.get(&price_per_nft).is_some();
+
+            if orderbook_line_exists {
+                let mut orderbook_line = self.ask_orderbook_lines.get_mut(&price_per_nft).unwrap();
+                orderbook_line.level_qty += 1;
+                orderbook_line.level_total += order_total;
+                Self::link_level_order(&mut self.level_links, &mut orderbook_line, &order_id);
+            } else {
+                let mut new_orderbook_line = OrderbookLine {
+                    level_price: price_per_nft,
+                    level_qty: 1,
+                    level_total: order_total,
+                    level_head: None,
+                    level_tail: None,
+                };
+
+                Self::link_level_order(&mut self.level_links, &mut new_orderbook_line, &order_id);
+
+                self.ask_orderbook_lines
+                    .insert(price_per_nft, new_orderbook_line);
+            }
+
+            // Update the lowest ask if the new ask is lower than the current lowest ask
+
+            if self.lowest_ask == dec!(0) || price_per_nft < self.lowest_ask {
+                self.lowest_ask = price_per_nft;
+            }
+
+            receipt
+        }
+
+        fn match_bids(
+            &mut self,
+            nfts: Bucket,
+            min_price: Decimal,
+        ) -> (Vec<Bucket>, Option<Vec<Bucket>>, Decimal) {
+            let mut payment_to_seller: Vec<Bucket> = vec![];
+
+            let nft_bucket_vec_holder: Vec<Bucket> = vec![nfts];
+            let mut nft_bucket_vec_holder = Some(nft_bucket_vec_holder);
+
+            // walk the price levels down from the best bid one at a time - the next lower level is only
+            // looked up once the current one is done, so a fill never loads levels it does not reach
+
+            let mut next_level = self.best_bid();
+
+            while let Some(price_level) = next_level {
+                // stop before matching any bid priced below the limit
+                if price_level < min_price {
+                    break;
+                }
+
+                // start at the head of the queue for the current price level
+                let mut next_order = {
+                    let orderline = self.orderbook_lines.get(&price_level).unwrap();
+                    orderline.level_head.clone()
+                };
+
+                // loop through the orders at this price level, fill the orders - if nft amount has not been filled, then move to the next bid
+                // or if it had been filled then continue with the rest of the function.
+                // The next order is looked up first, as filling or retiring the current order unlinks it from the queue.
+
+                while let Some(order_id) = next_order {
+                    next_order = self.level_links.get(&order_id).unwrap().next_order.clone();
+
+                    // expired bids are retired instead of filled - their owners reclaim the funds with claim
+
+                    if self.is_expired(&order_id) {
+                        self.retire_expired_bid(order_id);
+                        continue;
+                    }
+
+                    let seller_nfts = nft_bucket_vec_holder.take().unwrap();
+
+                    let (remaining_nfts, bid_value) =
+                        self.fill_order_partial_or_full(order_id.clone(), seller_nfts);
+
+                    payment_to_seller.extend(bid_value);
+
+                    match remaining_nfts {
+                        Some(nfts) => nft_bucket_vec_holder = Some(vec![nfts]),
+                        None => break,
+                    }
+                }
+
+                // If all NFTs have been filled, break out of the price level loop as well
+                if nft_bucket_vec_holder.is_none() {
+                    break;
+                }
+
+                next_level = self
+                    .price_levels
+                    .range_back(Decimal::MIN..price_level)
+                    .next()
+                    .map(|(price_level, _value, _next_key)| price_level);
+            }
+
+            // the best bid is read back from the price levels that are left
+
+            self.update_highest_bid();
+
+            let mut proceeds = Bucket::new(self.quote_resource);
+            for bid_value in payment_to_seller {
+                proceeds.put(bid_value);
+            }
+
+            let (proceeds, fee) = self.settle_proceeds(proceeds);
+
+            (vec![proceeds], nft_bucket_vec_holder, fee)
+        }
+
+        // Every sale pays the taker fee and then the creator royalty out of the seller's proceeds, whether the seller
+        // filled a bid or had an ask filled. The royalty is a share of what is left after the fee, so the two together
+        // never exceed the proceeds. Returns the net proceeds and the fee taken.
+
+        fn settle_proceeds(&mut self, mut proceeds: Bucket) -> (Bucket, Decimal) {
+            let fee = self.fee_amount(proceeds.amount());
+            self.fee_vault.put(proceeds.take(fee));
+
+            if let Some(royalty_config) = self.royalty_config() {
+                let royalty = proceeds
+                    .amount()
+                    .checked_mul(royalty_config.royalty_percentage)
+                    .unwrap()
+                    .checked_div(dec!(100))
+                    .unwrap()
+                    .checked_round(self.quote_divisibility, RoundingMode::ToZero)
+                    .unwrap();
+                self.deposit_royalty(royalty_config.royalty_badge, proceeds.take(royalty));
+            }
+
+            (proceeds, fee)
+        }
+
+        // The per-book override wins, otherwise the royalty is read from the collection's
+        // "royalty_percentage" (Decimal, 0 - 100) and "royalty_badge" (resource address) metadata
+
+        fn royalty_config(&self) -> Option<RoyaltyConfig> {
+            if self.royalty_override.is_some() {
+                return self.royalty_override.clone();
+            }
+
+            let collection_manager = ResourceManager::from(self.collection);
+
+            let royalty_percentage: Option<Decimal> = collection_manager
+                .get_metadata("royalty_percentage")
+                .ok()
+                .flatten();
+
+            let royalty_badge: Option<GlobalAddress> = collection_manager
+                .get_metadata("royalty_badge")
+                .ok()
+                .flatten();
+
+            match (royalty_percentage, royalty_badge) {
+                (Some(royalty_percentage), Some(royalty_badge))
+                    if royalty_percentage > dec!(0) && royalty_percentage <= dec!(100) =>
+                {
+                    ResourceAddress::try_from(royalty_badge.as_node_id().0)
+                        .ok()
+                        .map(|royalty_badge| RoyaltyConfig {
+                            royalty_percentage,
+                            royalty_badge,
+                        })
+                }
+                _ => None,
+            }
+        }
+
+        fn deposit_royalty(&mut self, royalty_badge: ResourceAddress, royalty: Bucket) {
+            let vault_exists = self.royalty_vaults.get(&royalty_badge).is_some();
+
+            if vault_exists {
+                let mut vault = self.royalty_vaults.get_mut(&royalty_badge).unwrap();
+                vault.put(royalty);
+            } else {
+                self.royalty_vaults
+                    .insert(royalty_badge, Vault::with_bucket(royalty));
+            }
+        }
+
+        // Price paid per NFT once the maker fee (positive) or rebate (negative) is applied
+
+        fn effective_price(&self, price: Decimal, maker_fee_bps: i32) -> Decimal {
+            price
+                .checked_mul(dec!(10000) + Decimal::from(maker_fee_bps))
+                .unwrap()
+                .checked_div(dec!(10000))
+                .unwrap()
+        }
+
+        // Quote escrowed per NFT - rebates are paid from the fee vault, so only a positive maker fee is escrowed
+
+        fn escrow_price(&self, price: Decimal) -> Decimal {
+            if self.maker_fee_bps > 0 {
+                self.effective_price(price, self.maker_fee_bps)
+            } else {
+                price
+            }
+        }
+
+        fn fee_amount(&self, amount: Decimal) -> Decimal {
+            amount
+                .checked_mul(Decimal::from(self.taker_fee_bps))
+                .unwrap()
+                .checked_div(dec!(10000))
+                .unwrap()
+                .checked_round(self.quote_divisibility, RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        fn match_asks(
+            &mut self,
+            payment: &mut FungibleBucket,
+            max_qty: u64,
+            max_price: Decimal,
+        ) -> (Vec<Bucket>, u64) {
+            let mut nfts_to_buyer: Vec<Bucket> = vec![];
+            let mut qty_left = max_qty;
+
+            // buy from the cheapest listing first, oldest order first within a price level
+
+            while qty_left > 0 {
+                let price_level = match self
+                    .ask_price_levels
+                    .range(Decimal::MIN..Decimal::MAX)
+                    .next()
+                {
+                    Some((price_level, _value, _next_key)) => price_level,
+                    None => break,
+                };
+
+                if price_level > max_price {
+                    break;
+                }
+
+                let order_id = {
+                    let orderline = self.ask_orderbook_lines.get(&price_level).unwrap();
+                    orderline.level_head.clone().unwrap()
+                };
+
+                let remaining_qty = self.ask_orders.get(&order_id).unwrap().remaining_qty;
+
+                let fill_qty =
+                    self.affordable_qty(payment.amount(), price_level, qty_left.min(remaining_qty));
+
+                // the cheapest listing is out of reach, so every other listing is too
+
+                if fill_qty == 0 {
+                    break;
+                }
+
+                nfts_to_buyer.push(self.fill_ask_order(order_id, fill_qty, payment));
+
+                qty_left -= fill_qty;
+            }
+
+            (nfts_to_buyer, max_qty - qty_left)
+        }
+
+        // The largest whole number of NFTs, up to max_qty, that the payment covers at the price
+
+        fn affordable_qty(&self, payment: Decimal, price: Decimal, max_qty: u64) -> u64 {
+            let mut low: u64 = 0;
+            let mut high: u64 = max_qty;
+
+            while low < high {
+                let mid = high - (high - low) / 2;
+                if self.quote_amount(mid, price) <= payment {
+                    low = mid;
+                } else {
+                    high = mid - 1;
+                }
+            }
+
+            low
+        }
+
+        fn fill_order_partial_or_full(
+            &mut self,
+            order_id: NonFungibleGlobalId,
+            mut seller_nfts: Vec<Bucket>,
+        ) -> (Option<Bucket>, Vec<Bucket>) {
+            let mut nfts = seller_nfts.pop().unwrap();
+
+            let order = self.orders.get(&order_id).unwrap().clone();
+
+            let remaining_qty = order.remaining_qty;
+
+            let nft_qty = Self::nft_count(&nfts);
+
+            assert!(
+                nft_qty > 0,
+                "[Fill Order Partial or Full] : NFT amount must be greater than 0"
+            );
+
+            // fill as many NFTs as the order still has room for - the payout only covers the NFTs delivered
+
+            let fill_qty = if nft_qty >= remaining_qty {
+                remaining_qty
+            } else {
+                nft_qty
+            };
+
+            let fill_value = self.quote_amount(fill_qty, order.order_price);
+
+            // the level total is the value of the NFTs still bid for at that level, at the order price - escrowed
+            // maker fees and rebates paid into the order vault are not part of it, the same as on the ask side
+            let listed_value_before = self.quote_amount(remaining_qty, order.order_price);
+
+            let mut bid_value: Vec<Bucket> = vec![];
+            {
+                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
+                bid_value.push(vault.take(fill_value));
+            }
+
+            // charge the maker fee from the escrow, or pay the maker rebate out of the fee vault
+
+            let maker_value = self.quote_amount(fill_qty, order.effective_price);
+
+            if order.maker_fee_bps > 0 {
+                let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
+                self.fee_vault.put(vault.take(maker_value - fill_value));
+            } else if order.maker_fee_bps < 0 {
+                // rebates are funded by the fees collected so far - if the fee vault runs short the maker
+                // gets what is there, and the event records the rebate due next to the amount paid
+                let rebate_due = fill_value - maker_value;
+                let rebate_paid = rebate_due.min(self.fee_vault.amount());
+                {
+                    let mut vault = self.bid_vaults.get_mut(&order_id).unwrap();
+                    vault.put(self.fee_vault.take(rebate_paid));
+                }
+                Runtime::emit_event(MakerRebatePaid {
+                    order_id: order_id.clone(),
+                    rebate_due,
+                    rebate_paid,
+                });
+            }
+
+            let nft_order_fill = nfts.take(Decimal::from(fill_qty));
+
+            // place nfts into filled orders vault, adding to the NFTs delivered by earlier partial fills
+            self.deposit_filled_nfts(order_id.clone(), nft_order_fill);
+
+            if fill_qty == remaining_qty {
+                // update nft receipt order status to filled
+                {
+                    self.update_order_to_filled(order_id.clone(), fill_qty, order.order_price);
+                }
+
+                Runtime::emit_event(BidFilled {
+                    order_id: order_id.clone(),
+                    order_price: order.order_price,
+                    fill_qty,
+                    fill_value,
+                    order_sequence: order.order_sequence,
+                });
+
+                // remove order from bids key value store
+                {
+                    self.remove_order(order_id.clone());
+                }
+
+                // take the order out of its price level, pruning the level if it was the last order
+                // any quote dust left in the order vault is no longer on the book and is released through claim
+                self.remove_from_price_level(order.order_price, &order_id, listed_value_before);
+            } else {
+                // update order status to partial
+                {
+                    self.update_order_to_partial(order_id.clone(), fill_qty, order.order_price);
+                }
+
+                Runtime::emit_event(BidPartiallyFilled {
+                    order_id: order_id.clone(),
+                    order_price: order.order_price,
+                    fill_qty,
+                    fill_value,
+                    filled_qty: order.filled_qty + fill_qty,
+                    remaining_qty: order.remaining_qty - fill_qty,
+                    order_sequence: order.order_sequence,
+                });
+
+                // the order keeps its place in the queue - only the level total changes
+                let listed_value_after =
+                    self.quote_amount(remaining_qty - fill_qty, order.order_price);
+                {
+                    let mut orderline = self.orderbook_lines.get_mut(&order.order_price).unwrap();
+                    orderline.level_total -= listed_value_before - listed_value_after;
+                }
+            }
+
+            if nfts.is_empty() {
+                nfts.drop_empty();
+                (None, bid_value)
+            } else {
+                (Some(nfts), bid_value)
+            }
+        }
+
+        fn is_expired(&self, order_id: &NonFungibleGlobalId) -> bool {
+            match self.orders.get(order_id).unwrap().expires_at {
+                Some(expires_at) => Clock::current_time_is_at_or_after(
+                    Instant::new(expires_at),
+                    TimePrecision::Minute,
+                ),
+                None => false,
+            }
+        }
+
+        // Takes an expired bid off the book. Its quote and any NFTs from earlier partial fills
+        // stay in the order vaults until the receipt is presented to claim.
+
+        fn retire_expired_bid(&mut self, order_id: NonFungibleGlobalId) {
+            let order = self.orders.get(&order_id).unwrap().clone();
+
+            let remaining_value = self.quote_amount(order.remaining_qty, order.order_price);
+
+            self.remove_order(order_id.clone());
+
+            self.remove_from_price_level(order.order_price, &order_id, remaining_value);
+
+            Runtime::emit_event(BidExpired {
+                order_id,
+                order_price: order.order_price,
+                remaining_qty: order.remaining_qty,
+                order_sequence: order.order_sequence,
+            });
+        }
+
+        fn deposit_filled_nfts(&mut self, order_id: NonFungibleGlobalId, nfts: Bucket) {
+            let vault_exists = self.nft_vaults.get(&order_id).is_some();
+
+            if vault_exists {
+                let mut vault = self.nft_vaults.get_mut(&order_id).unwrap();
+                vault.put(nfts);
+            } else {
+                self.nft_vaults.insert(order_id, Vault::with_bucket(nfts));
+            }
+        }
+
+        fn nft_count(nfts: &Bucket) -> u64 {
+            nfts.as_non_fungible().non_fungible_local_ids().len() as u64
+        }
+
+        fn is_quotable_price(&self, price: Decimal) -> bool {
+            price
+                .checked_round(self.quote_divisibility, RoundingMode::ToZero)
+                .unwrap()
+                == price
+        }
+
+        fn is_on_tick(&self, price: Decimal) -> bool {
+            price
+                .checked_div(self.order_limits.tick_size)
+                .unwrap()
+                .checked_round(0, RoundingMode::ToZero)
+                .unwrap()
+                .checked_mul(self.order_limits.tick_size)
+                .unwrap()
+                == price
+        }
+
+        // Quote value of a whole number of NFTs, rounded down to what the quote resource can hold
+
+        fn quote_amount(&self, qty: u64, price: Decimal) -> Decimal {
+            Decimal::from(qty)
+                .checked_mul(price)
+                .unwrap()
+                .checked_round(self.quote_divisibility, RoundingMode::ToZero)
+                .unwrap()
+        }
+
+        // The clock only has minute precision, so the sequence breaks ties between orders placed in the same minute.
+        // It is shared by bids and asks and increases with every order, which keeps (time, sequence) strictly ordered.
+
+        fn next_order_time_and_sequence(&mut self) -> (i64, u64) {
+            let order_time = Clock::current_time(TimePrecision::Minute).seconds_since_unix_epoch;
+
+            self.sequence_number += 1;
+
+            (order_time, self.sequence_number)
+        }
+
+        fn fill_ask_order(
+            &mut self,
+            order_id: NonFungibleGlobalId,
+            fill_qty: u64,
+            payment: &mut FungibleBucket,
+        ) -> Bucket {
+            let order = self.ask_orders.get(&order_id).unwrap().clone();
+
+            let fill_value = self.quote_amount(fill_qty, order.order_price);
+
+            let nfts = {
+                let mut vault = self.ask_nft_vaults.get_mut(&order_id).unwrap();
+                vault.take(Decimal::from(fill_qty))
+            };
+
+            // the buyer pays the listed price, the seller collects it net of the taker fee and royalty
+
+            let (proceeds, _fee) = self.settle_proceeds(payment.take(fill_value).into());
+            {
+                let mut vault = self.ask_vaults.get_mut(&order_id).unwrap();
+                vault.put(proceeds);
+            }
+
+            let order_status = if fill_qty == order.remaining_qty {
+                OrderStatus::FILLED
+            } else {
+                OrderStatus::PARTIAL
+            };
+
+            {
+                let mut order = self.ask_orders.get_mut(&order_id).unwrap();
+                order.order_status = order_status.clone();
+                order.filled_qty += fill_qty;
+                order.remaining_qty -= fill_qty;
+            }
+
+            self.update_receipt(&order_id, order_status.clone(), fill_qty, order.order_price);
+
+            // the level total is the value of the NFTs still listed at that level, so the order's share is
+            // recomputed from its remaining qty instead of decremented by the rounded value of each fill
+
+            let listed_value_before = self.quote_amount(order.remaining_qty, order.order_price);
+            let listed_value_after =
+                self.quote_amount(order.remaining_qty - fill_qty, order.order_price);
+
+            if order_status == OrderStatus::FILLED {
+                self.ask_orders.remove(&order_id);
+                self.remove_from_ask_price_level(order.order_price, &order_id, listed_value_before);
+            } else {
+                let mut orderline = self
+                    .ask_orderbook_lines
+                    .get_mut(&order.order_price)
+                    .unwrap();
+                orderline.level_total -= listed_value_before - listed_value_after;
+            }
+
+            nfts
+        }
+
+        fn remove_from_ask_price_level(
+            &mut self,
+            price_level: Decimal,
+            order_id: &NonFungibleGlobalId,
+            amount: Decimal,
+        ) {
+            let level_empty = match self.ask_orderbook_lines.get_mut(&price_level) {
+                Some(mut orderline) => {
+                    Self::unlink_level_order(&mut self.level_links, &mut orderline, order_id);
+                    orderline.level_qty -= 1;
+                    orderline.level_total -= amount;
+                    orderline.level_head.is_none()
+                }
+                None => false,
+            };
+
+            // prune the ask price level once its last order is gone
+
+            if level_empty {
+                self.ask_price_levels.remove(&price_level);
+                self.ask_orderbook_lines.remove(&price_level);
+                self.lowest_ask = self
+                    .ask_price_levels
+                    .range(Decimal::MIN..Decimal::MAX)
+                    .next()
+                    .map(|(price_level, _value, _next_key)| price_level)
+                    .unwrap_or(dec!(0));
+            }
+        }
+
+        fn remove_from_price_level(
+            &mut self,
+            price_level: Decimal,
+            order_id: &NonFungibleGlobalId,
+            amount: Decimal,
+        ) {
+            let level_empty = match self.orderbook_lines.get_mut(&price_level) {
+                Some(mut orderline) => {
+                    Self::unlink_level_order(&mut self.level_links, &mut orderline, order_id);
+                    orderline.level_qty -= 1;
+                    orderline.level_total -= amount;
+                    orderline.level_head.is_none()
+                }
+                None => false,
+            };
+
+            // prune the price level once its last order is gone
+
+            if level_empty {
+                self.price_levels.remove(&price_level);
+                self.orderbook_lines.remove(&price_level);
+                self.update_highest_bid();
+                Runtime::emit_event(PriceLevelRemoved {
+                    level_price: price_level,
+                });
+            }
+        }
+
+        // Appends an order to the tail of its price level queue
+
+        fn link_level_order(
+            level_links: &mut KeyValueStore<NonFungibleGlobalId, LevelLink>,
+            orderline: &mut OrderbookLine,
+            order_id: &NonFungibleGlobalId,
+        ) {
+            match &orderline.level_tail {
+                Some(tail) => {
+                    let mut tail_link = level_links.get_mut(tail).unwrap();
+                    tail_link.next_order = Some(order_id.clone());
+                }
+                None => orderline.level_head = Some(order_id.clone()),
+            }
+
+            level_links.insert(
+                order_id.clone(),
+                LevelLink {
+                    prev_order: orderline.level_tail.clone(),
+                    next_order: None,
+                },
+            );
+
+            orderline.level_tail = Some(order_id.clone());
+        }
+
+        // Takes an order out of its price level queue, wherever it sits, by joining its neighbours
+
+        fn unlink_level_order(
+            level_links: &mut KeyValueStore<NonFungibleGlobalId, LevelLink>,
+            orderline: &mut OrderbookLine,
+            order_id: &NonFungibleGlobalId,
+        ) {
+            let link = level_links.get(order_id).unwrap().clone();
+
+            match &link.prev_order {
+                Some(prev_order) => {
+                    let mut prev_link = level_links.get_mut(prev_order).unwrap();
+                    prev_link.next_order = link.next_order.clone();
+                }
+                None => orderline.level_head = link.next_order.clone(),
+            }
+
+            match &link.next_order {
+                Some(next_order) => {
+                    let mut next_link = level_links.get_mut(next_order).unwrap();
+                    next_link.prev_order = link.prev_order.clone();
+                }
+                None => orderline.level_tail = link.prev_order.clone(),
+            }
+
+            level_links.remove(order_id);
+        }
+
+        fn update_highest_bid(&mut self) {
+            self.highest_bid = self
+                .price_levels
+                .range_back(Decimal::MIN..Decimal::MAX)
+                .next()
+                .map(|(price_level, _value, _next_key)| price_level)
+                .unwrap_or(dec!(0));
+        }
+
+        fn update_order_to_filled(
+            &mut self,
+            order_id: NonFungibleGlobalId,
+            fill_qty: u64,
+            fill_price: Decimal,
+        ) {
+            {
+                let mut order = self.orders.get_mut(&order_id).unwrap();
+                order.order_status = OrderStatus::FILLED;
+                order.filled_qty += fill_qty;
+                order.remaining_qty -= fill_qty;
+            }
+            self.update_receipt(&order_id, OrderStatus::FILLED, fill_qty, fill_price);
+        }
+
+        fn update_order_to_partial(
+            &mut self,
+            order_id: NonFungibleGlobalId,
+            fill_qty: u64,
+            fill_price: Decimal,
+        ) {
+            {
+                let mut order = self.orders.get_mut(&order_id).unwrap();
+                order.order_status = OrderStatus::PARTIAL;
+                order.filled_qty += fill_qty;
+                order.remaining_qty -= fill_qty;
+            }
+            self.update_receipt(&order_id, OrderStatus::PARTIAL, fill_qty, fill_price);
+        }
+
+        // Mirror the order state onto the receipt NFT so it can be read without querying component state
+
+        fn update_receipt(
+            &self,
+            order_id: &NonFungibleGlobalId,
+            order_status: OrderStatus,
+            fill_qty: u64,
+            fill_price: Decimal,
+        ) {
+            let local_id = order_id.local_id();
+
+            let receipt_generator =
+                if order_id.resource_address() == self.ask_receipt_resource_address {
+                    &self.ask_receipt_generator
+                } else {
+                    &self.receipt_generator
+                };
+
+            let receipt: OrderReceipt = receipt_generator.get_non_fungible_data(local_id);
+
+            let filled_qty = receipt.filled_qty + fill_qty;
+
+            let filled_value = receipt.avg_fill_price * Decimal::from(receipt.filled_qty)
+                + fill_price * Decimal::from(fill_qty);
+
+            let avg_fill_price = filled_value.checked_div(Decimal::from(filled_qty)).unwrap();
+
+            receipt_generator.update_non_fungible_data(local_id, "order_status", order_status);
+            receipt_generator.update_non_fungible_data(local_id, "filled_qty", filled_qty);
+            receipt_generator.update_non_fungible_data(
+                local_id,
+                "remaining_qty",
+                receipt.order_qty - filled_qty,
+            );
+            receipt_generator.update_non_fungible_data(local_id, "avg_fill_price", avg_fill_price);
         }
     }
 }
@@ -1 +1,2 @@
+pub mod bidbook_factory;
 pub mod hello_swap;
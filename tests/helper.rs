@@ -1,13 +1,22 @@
+use hello_swap::bidbook_factory::BidbookEntry;
+use hello_swap::hello_swap::{DepthLevel, Order, OrderReceipt};
 use lazy_static::lazy_static;
 use radix_engine::{
     blueprints::package::PackageDefinition,
-    system::system_modules::execution_trace::ResourceSpecifier::Amount,
+    system::system_modules::execution_trace::ResourceSpecifier::{self, Amount, Ids},
 };
 use scrypto::prelude::*;
 use scrypto_testenv::*;
 use std::mem;
 use transaction::builder::ManifestBuilder;
 
+pub const MAX_FEE_BPS: u32 = 500;
+pub const TICK_SIZE: Decimal = dec!(0.1);
+pub const MIN_ORDER_VALUE: Decimal = dec!(1);
+pub const MIN_ORDER_QTY: u64 = 1;
+// Encoded the same as the blueprint's OrderLimits struct (tick_size, min_order_value, min_order_qty)
+pub const ORDER_LIMITS: (Decimal, Decimal, u64) = (TICK_SIZE, MIN_ORDER_VALUE, MIN_ORDER_QTY);
+
 lazy_static! {
     static ref PACKAGE: (Vec<u8>, PackageDefinition) = compile_package(this_package!());
 }
@@ -18,9 +27,17 @@ impl TestHelperExecution for HelloSwapTestHelper {
     }
 }
 
+#[derive(ScryptoSbor, ManifestSbor, NonFungibleData)]
+pub struct Collectible {
+    pub edition: u64,
+}
+
 pub struct HelloSwapTestHelper {
     env: TestEnvironment,
     component_address: Option<ComponentAddress>,
+    factory_address: Option<ComponentAddress>,
+    quote_address: ResourceAddress,
+    round: u64,
 }
 
 impl HelloSwapTestHelper {
@@ -30,18 +47,45 @@ impl HelloSwapTestHelper {
         HelloSwapTestHelper {
             env,
             component_address: None,
+            factory_address: None,
+            quote_address: XRD,
+            round: 0,
         }
     }
 
-    pub fn instantiate(&mut self, x_address: ResourceAddress) -> &mut HelloSwapTestHelper {
+    pub fn instantiate(
+        &mut self,
+        x_address: ResourceAddress,
+        quote_address: ResourceAddress,
+        owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        // no royalty override - royalties are read from the collection metadata
+        self.instantiate_with_royalty_override(x_address, quote_address, owner_badge, None)
+    }
+
+    // The override is passed as (royalty_percentage, royalty_badge), the tuple a RoyaltyConfig encodes to
+    pub fn instantiate_with_royalty_override(
+        &mut self,
+        x_address: ResourceAddress,
+        quote_address: ResourceAddress,
+        owner_badge: Option<ResourceAddress>,
+        royalty_override: Option<(Decimal, ResourceAddress)>,
+    ) -> &mut HelloSwapTestHelper {
         // with the next ManifestBuilder update this can be simplified to
         // let manifest_builder = mem::take(&mut self.environment.manifest_builder);
         let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
         self.env.manifest_builder = manifest_builder.call_function(
             self.env.package_address("hello_swap"),
             "HelloSwap",
-            "instantiate_bidbook_collection",
-            manifest_args!(x_address),
+            "instantiate_collection_bidbook",
+            manifest_args!(
+                x_address,
+                quote_address,
+                owner_badge,
+                MAX_FEE_BPS,
+                royalty_override,
+                ORDER_LIMITS
+            ),
         );
         // To support instruction labels we are tracking:
         // instruction_count = the total amount of new instructions added in this function
@@ -51,6 +95,568 @@ impl HelloSwapTestHelper {
         self
     }
 
+    pub fn instantiate_factory(&mut self, owner_badge: ResourceAddress, verbose: bool) -> Receipt {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_function(
+            self.env.package_address("hello_swap"),
+            "BidbookFactory",
+            "instantiate_bidbook_factory",
+            manifest_args!(owner_badge),
+        );
+        self.env.new_instruction("instantiate_factory", 1, 0);
+        let receipt = self.execute_expect_success(verbose);
+        let factory_address: ComponentAddress = receipt.outputs("instantiate_factory")[0];
+        self.factory_address = Some(factory_address);
+        receipt
+    }
+
+    pub fn create_bidbook(
+        &mut self,
+        x_address: ResourceAddress,
+        quote_address: ResourceAddress,
+        factory_owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        let royalty_override: Option<()> = None;
+        let owner_badge: Option<ResourceAddress> = None;
+        let mut manifest_builder =
+            mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let mut instruction_count = 1;
+        if let Some(factory_owner_badge) = factory_owner_badge {
+            manifest_builder = manifest_builder.create_proof_from_account_of_amount(
+                self.env.account,
+                factory_owner_badge,
+                dec!(1),
+            );
+            instruction_count += 1;
+        }
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.factory_address.unwrap(),
+            "create_bidbook",
+            manifest_args!(
+                x_address,
+                quote_address,
+                owner_badge,
+                MAX_FEE_BPS,
+                royalty_override,
+                ORDER_LIMITS
+            ),
+        );
+        self.env
+            .new_instruction("create_bidbook", instruction_count, instruction_count - 1);
+        self
+    }
+
+    pub fn get_bidbook(
+        &mut self,
+        x_address: ResourceAddress,
+        quote_address: ResourceAddress,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.factory_address.unwrap(),
+            "get_bidbook",
+            manifest_args!(x_address, quote_address),
+        );
+        self.env.new_instruction("get_bidbook", 1, 0);
+        self
+    }
+
+    pub fn get_bidbooks(&mut self, start: u64, limit: u64) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.factory_address.unwrap(),
+            "get_bidbooks",
+            manifest_args!(start, limit),
+        );
+        self.env.new_instruction("get_bidbooks", 1, 0);
+        self
+    }
+
+    pub fn get_collection_bidbooks(
+        &mut self,
+        x_address: ResourceAddress,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.factory_address.unwrap(),
+            "get_collection_bidbooks",
+            manifest_args!(x_address),
+        );
+        self.env.new_instruction("get_collection_bidbooks", 1, 0);
+        self
+    }
+
+    pub fn get_bidbook_count(&mut self) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.factory_address.unwrap(),
+            "get_bidbook_count",
+            manifest_args!(),
+        );
+        self.env.new_instruction("get_bidbook_count", 1, 0);
+        self
+    }
+
+    pub fn admin_call(
+        &mut self,
+        method_name: &str,
+        owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        let mut manifest_builder =
+            mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let mut instruction_count = 1;
+        if let Some(owner_badge) = owner_badge {
+            manifest_builder = manifest_builder.create_proof_from_account_of_amount(
+                self.env.account,
+                owner_badge,
+                dec!(1),
+            );
+            instruction_count += 1;
+        }
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            method_name,
+            manifest_args!(),
+        );
+        self.env
+            .new_instruction(method_name, instruction_count, instruction_count - 1);
+        self
+    }
+
+    pub fn set_book_metadata(
+        &mut self,
+        key: &str,
+        value: &str,
+        owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        let mut manifest_builder =
+            mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let mut instruction_count = 1;
+        if let Some(owner_badge) = owner_badge {
+            manifest_builder = manifest_builder.create_proof_from_account_of_amount(
+                self.env.account,
+                owner_badge,
+                dec!(1),
+            );
+            instruction_count += 1;
+        }
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "set_book_metadata",
+            manifest_args!(key.to_string(), value.to_string()),
+        );
+        self.env.new_instruction(
+            "set_book_metadata",
+            instruction_count,
+            instruction_count - 1,
+        );
+        self
+    }
+
+    pub fn set_fee_schedule(
+        &mut self,
+        maker_fee_bps: i32,
+        taker_fee_bps: u32,
+        owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        let mut manifest_builder =
+            mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let mut instruction_count = 1;
+        if let Some(owner_badge) = owner_badge {
+            manifest_builder = manifest_builder.create_proof_from_account_of_amount(
+                self.env.account,
+                owner_badge,
+                dec!(1),
+            );
+            instruction_count += 1;
+        }
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "set_fee_schedule",
+            manifest_args!(maker_fee_bps, taker_fee_bps),
+        );
+        self.env
+            .new_instruction("set_fee_schedule", instruction_count, instruction_count - 1);
+        self
+    }
+
+    pub fn set_order_limits(
+        &mut self,
+        tick_size: Decimal,
+        min_order_value: Decimal,
+        min_order_qty: u64,
+        owner_badge: Option<ResourceAddress>,
+    ) -> &mut HelloSwapTestHelper {
+        let mut manifest_builder =
+            mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let mut instruction_count = 1;
+        if let Some(owner_badge) = owner_badge {
+            manifest_builder = manifest_builder.create_proof_from_account_of_amount(
+                self.env.account,
+                owner_badge,
+                dec!(1),
+            );
+            instruction_count += 1;
+        }
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "set_order_limits",
+            manifest_args!((tick_size, min_order_value, min_order_qty)),
+        );
+        self.env
+            .new_instruction("set_order_limits", instruction_count, instruction_count - 1);
+        self
+    }
+
+    pub fn place_bid(
+        &mut self,
+        amount: Decimal,
+        qty: u64,
+        price_per_nft: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        self.place_bid_until(amount, qty, price_per_nft, None)
+    }
+
+    pub fn place_bid_until(
+        &mut self,
+        amount: Decimal,
+        qty: u64,
+        price_per_nft: Decimal,
+        expires_at: Option<i64>,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, self.quote_address, amount)
+            .take_from_worktop(self.quote_address, amount, self.name("bid_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let bid_bucket = lookup.bucket(self.name("bid_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "place_bid",
+                    manifest_args!(bid_bucket, qty, price_per_nft, expires_at),
+                )
+            });
+        self.env.new_instruction("place_bid", 3, 2);
+        self
+    }
+
+    pub fn fill_bid(
+        &mut self,
+        nft_address: ResourceAddress,
+        nft_amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        self.fill_bid_with_limits(nft_address, nft_amount, dec!(0), None)
+    }
+
+    pub fn fill_bid_with_limits(
+        &mut self,
+        nft_address: ResourceAddress,
+        nft_amount: Decimal,
+        min_price_per_nft: Decimal,
+        min_total_proceeds: Option<Decimal>,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, nft_address, nft_amount)
+            .take_from_worktop(nft_address, nft_amount, self.name("nft_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let nft_bucket = lookup.bucket(self.name("nft_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "fill_bid",
+                    manifest_args!(nft_bucket, min_price_per_nft, min_total_proceeds),
+                )
+            });
+        self.env.new_instruction("fill_bid", 3, 2);
+        self
+    }
+
+    pub fn fill_bid_exact(
+        &mut self,
+        nft_address: ResourceAddress,
+        nft_amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        let min_total_proceeds: Option<Decimal> = None;
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, nft_address, nft_amount)
+            .take_from_worktop(nft_address, nft_amount, self.name("nft_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let nft_bucket = lookup.bucket(self.name("nft_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "fill_bid_exact",
+                    manifest_args!(nft_bucket, dec!(0), min_total_proceeds),
+                )
+            });
+        self.env.new_instruction("fill_bid_exact", 3, 2);
+        self
+    }
+
+    pub fn place_ask(
+        &mut self,
+        nft_amount: Decimal,
+        price_per_nft: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        let nft_address = self.j_nft_address();
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, nft_address, nft_amount)
+            .take_from_worktop(nft_address, nft_amount, self.name("nft_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let nft_bucket = lookup.bucket(self.name("nft_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "place_ask",
+                    manifest_args!(nft_bucket, price_per_nft),
+                )
+            });
+        self.env.new_instruction("place_ask", 3, 2);
+        self
+    }
+
+    pub fn fill_ask(&mut self, amount: Decimal, max_qty: u64) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, XRD, amount)
+            .take_from_worktop(XRD, amount, self.name("payment_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let payment_bucket = lookup.bucket(self.name("payment_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "fill_ask",
+                    manifest_args!(payment_bucket, max_qty),
+                )
+            });
+        self.env.new_instruction("fill_ask", 3, 2);
+        self
+    }
+
+    // Expects the account to hold exactly one receipt of the resource
+    pub fn cancel_bid(&mut self, receipt_address: ResourceAddress) -> &mut HelloSwapTestHelper {
+        self.receipt_call("cancel_bid", receipt_address, dec!(1))
+    }
+
+    pub fn cancel_bid_by_id(&mut self, order_id: &NonFungibleGlobalId) -> &mut HelloSwapTestHelper {
+        self.receipt_call_by_id("cancel_bid", order_id)
+    }
+
+    pub fn cancel_ask(&mut self, receipt_address: ResourceAddress) -> &mut HelloSwapTestHelper {
+        self.receipt_call("cancel_ask", receipt_address, dec!(1))
+    }
+
+    pub fn claim_ask(
+        &mut self,
+        receipt_address: ResourceAddress,
+        amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        self.receipt_call("claim_ask", receipt_address, amount)
+    }
+
+    pub fn claim(
+        &mut self,
+        receipt_address: ResourceAddress,
+        amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        self.receipt_call("claim", receipt_address, amount)
+    }
+
+    pub fn claim_royalties(&mut self, royalty_badge: ResourceAddress) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .create_proof_from_account_of_amount(self.env.account, royalty_badge, dec!(1))
+            .pop_from_auth_zone(self.name("royalty_proof"))
+            .with_name_lookup(|builder, lookup| {
+                let royalty_proof = lookup.proof(self.name("royalty_proof"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    "claim_royalties",
+                    manifest_args!(royalty_proof),
+                )
+            });
+        self.env.new_instruction("claim_royalties", 3, 2);
+        self
+    }
+
+    pub fn emergency_withdraw(
+        &mut self,
+        receipt_address: ResourceAddress,
+        amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        self.receipt_call("emergency_withdraw", receipt_address, amount)
+    }
+
+    // Withdraws an amount of receipts (or any other resource) and passes them to a receipt taking method
+    pub fn receipt_call(
+        &mut self,
+        method_name: &str,
+        receipt_address: ResourceAddress,
+        amount: Decimal,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, receipt_address, amount)
+            .take_from_worktop(receipt_address, amount, self.name("receipt_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let receipt_bucket = lookup.bucket(self.name("receipt_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    method_name,
+                    manifest_args!(receipt_bucket),
+                )
+            });
+        self.env.new_instruction(method_name, 3, 2);
+        self
+    }
+
+    pub fn retire_expired_bids(
+        &mut self,
+        order_ids: Vec<NonFungibleGlobalId>,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "retire_expired_bids",
+            manifest_args!(order_ids),
+        );
+        self.env.new_instruction("retire_expired_bids", 1, 0);
+        self
+    }
+
+    // Withdraws one specific receipt and passes it to a receipt taking method
+    pub fn receipt_call_by_id(
+        &mut self,
+        method_name: &str,
+        order_id: &NonFungibleGlobalId,
+    ) -> &mut HelloSwapTestHelper {
+        let receipt_address = order_id.resource_address();
+        let local_ids = BTreeSet::from([order_id.local_id().clone()]);
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .withdraw_non_fungibles_from_account(self.env.account, receipt_address, &local_ids)
+            .take_all_from_worktop(receipt_address, self.name("receipt_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let receipt_bucket = lookup.bucket(self.name("receipt_bucket"));
+                builder.call_method(
+                    self.component_address.unwrap(),
+                    method_name,
+                    manifest_args!(receipt_bucket),
+                )
+            });
+        self.env.new_instruction(method_name, 3, 2);
+        self
+    }
+
+    pub fn get_depth(&mut self, levels: u32) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_depth",
+            manifest_args!(levels),
+        );
+        self.env.new_instruction("get_depth", 1, 0);
+        self
+    }
+
+    pub fn get_ask_depth(&mut self, levels: u32) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_ask_depth",
+            manifest_args!(levels),
+        );
+        self.env.new_instruction("get_ask_depth", 1, 0);
+        self
+    }
+
+    pub fn best_bid(&mut self) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "best_bid",
+            manifest_args!(),
+        );
+        self.env.new_instruction("best_bid", 1, 0);
+        self
+    }
+
+    pub fn best_ask(&mut self) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "best_ask",
+            manifest_args!(),
+        );
+        self.env.new_instruction("best_ask", 1, 0);
+        self
+    }
+
+    pub fn spread(&mut self) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "spread",
+            manifest_args!(),
+        );
+        self.env.new_instruction("spread", 1, 0);
+        self
+    }
+
+    pub fn get_order(&mut self, order_id: NonFungibleGlobalId) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_order",
+            manifest_args!(order_id),
+        );
+        self.env.new_instruction("get_order", 1, 0);
+        self
+    }
+
+    pub fn get_orders_at_level(
+        &mut self,
+        price: Decimal,
+        cursor: Option<NonFungibleGlobalId>,
+        limit: u32,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_orders_at_level",
+            manifest_args!(price, cursor, limit),
+        );
+        self.env.new_instruction("get_orders_at_level", 1, 0);
+        self
+    }
+
+    pub fn get_orders_by_ids(
+        &mut self,
+        order_ids: Vec<NonFungibleGlobalId>,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_orders_by_ids",
+            manifest_args!(order_ids),
+        );
+        self.env.new_instruction("get_orders_by_ids", 1, 0);
+        self
+    }
+
+    pub fn get_queue_position(
+        &mut self,
+        order_id: NonFungibleGlobalId,
+    ) -> &mut HelloSwapTestHelper {
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder.call_method(
+            self.component_address.unwrap(),
+            "get_queue_position",
+            manifest_args!(order_id),
+        );
+        self.env.new_instruction("get_queue_position", 1, 0);
+        self
+    }
+
     // pub fn swap(
     //     &mut self,
     //     x_address: ResourceAddress,
@@ -68,11 +674,64 @@ impl HelloSwapTestHelper {
     //     self
     // }
 
+    // Moves the ledger clock forward - every call starts a new consensus round
+    pub fn advance_clock_to(&mut self, seconds_since_unix_epoch: i64) {
+        self.round += 1;
+        self.env
+            .test_runner
+            .advance_to_round_at_timestamp(Round::of(self.round), seconds_since_unix_epoch * 1000);
+    }
+
     pub fn instantiate_default(&mut self, x_address: ResourceAddress, verbose: bool) -> Receipt {
-        self.instantiate(x_address);
+        self.instantiate_quoted(x_address, XRD, None, verbose)
+    }
+
+    pub fn instantiate_owned(
+        &mut self,
+        x_address: ResourceAddress,
+        owner_badge: ResourceAddress,
+        verbose: bool,
+    ) -> Receipt {
+        self.instantiate_quoted(x_address, XRD, Some(owner_badge), verbose)
+    }
+
+    // Bids placed through the helper are paid in the quote resource of the book
+    pub fn instantiate_quoted(
+        &mut self,
+        x_address: ResourceAddress,
+        quote_address: ResourceAddress,
+        owner_badge: Option<ResourceAddress>,
+        verbose: bool,
+    ) -> Receipt {
+        self.instantiate(x_address, quote_address, owner_badge);
+        self.execute_instantiate(quote_address, verbose)
+    }
+
+    pub fn instantiate_with_royalty(
+        &mut self,
+        x_address: ResourceAddress,
+        owner_badge: Option<ResourceAddress>,
+        royalty_percentage: Decimal,
+        royalty_badge: ResourceAddress,
+        verbose: bool,
+    ) -> Receipt {
+        self.instantiate_with_royalty_override(
+            x_address,
+            XRD,
+            owner_badge,
+            Some((royalty_percentage, royalty_badge)),
+        );
+        self.execute_instantiate(XRD, verbose)
+    }
+
+    fn execute_instantiate(&mut self, quote_address: ResourceAddress, verbose: bool) -> Receipt {
         let receipt = self.execute_expect_success(verbose);
-        let component_address: ComponentAddress = receipt.outputs("instantiate")[0];
+        // instantiate returns the bidbook together with the newly minted owner badge, if any
+        let (component_address, _new_owner_badge) = receipt
+            .outputs::<(ComponentAddress, Option<Bucket>)>("instantiate")
+            .remove(0);
         self.component_address = Some(component_address);
+        self.quote_address = quote_address;
         receipt
     }
 
@@ -109,9 +768,9 @@ impl HelloSwapTestHelper {
     //     self.env.b_address
     // }
 
-    // pub fn x_address(&self) -> ResourceAddress {
-    //     self.env.x_address
-    // }
+    pub fn x_address(&self) -> ResourceAddress {
+        self.env.x_address
+    }
 
     // pub fn y_address(&self) -> ResourceAddress {
     //     self.env.y_address
@@ -121,17 +780,92 @@ impl HelloSwapTestHelper {
     //     self.env.v_address
     // }
 
-    // pub fn u_address(&self) -> ResourceAddress {
-    //     self.env.u_address
-    // }
+    // Decodes the events of one type emitted by a transaction, in the order they were emitted
+    pub fn events<T: ScryptoDecode>(&mut self, receipt: &Receipt, event_name: &str) -> Vec<T> {
+        receipt
+            .execution_receipt
+            .expect_commit_success()
+            .application_events
+            .iter()
+            .filter(|(event_type_identifier, _event_data)| {
+                self.env.test_runner.event_name(event_type_identifier) == event_name
+            })
+            .map(|(_event_type_identifier, event_data)| scrypto_decode::<T>(event_data).unwrap())
+            .collect()
+    }
 
-    // pub fn j_nft_address(&self) -> ResourceAddress {
-    //     self.env.j_nft_address
-    // }
+    // Amount of a resource held by the test account - the number of NFTs for non fungibles
+    pub fn balance(&mut self, resource_address: ResourceAddress) -> Decimal {
+        self.env
+            .test_runner
+            .get_component_balance(self.env.account, resource_address)
+    }
 
-    // pub fn k_nft_address(&self) -> ResourceAddress {
-    //     self.env.k_nft_address
-    // }
+    // Reads the receipt NFT data straight from the ledger
+    pub fn receipt_data(&mut self, order_id: &NonFungibleGlobalId) -> OrderReceipt {
+        self.env.test_runner.get_non_fungible_data::<OrderReceipt>(
+            order_id.resource_address(),
+            order_id.local_id().clone(),
+        )
+    }
+
+    // Creates a collection owned by the owner badge, with three NFTs in the test account,
+    // and sets the royalty metadata a bidbook reads when it has no royalty override
+    pub fn create_royalty_collection(
+        &mut self,
+        owner_badge: ResourceAddress,
+        royalty_percentage: Decimal,
+        royalty_badge: ResourceAddress,
+    ) -> ResourceAddress {
+        let collection = self
+            .env
+            .test_runner
+            .create_freely_mintable_and_burnable_non_fungible_resource(
+                OwnerRole::Fixed(rule!(require(owner_badge))),
+                NonFungibleIdType::Integer,
+                Some(vec![
+                    (NonFungibleLocalId::integer(1), Collectible { edition: 1 }),
+                    (NonFungibleLocalId::integer(2), Collectible { edition: 2 }),
+                    (NonFungibleLocalId::integer(3), Collectible { edition: 3 }),
+                ]),
+                self.env.account,
+            );
+        let manifest_builder = mem::replace(&mut self.env.manifest_builder, ManifestBuilder::new());
+        self.env.manifest_builder = manifest_builder
+            .create_proof_from_account_of_amount(self.env.account, owner_badge, dec!(1))
+            .set_metadata(collection, "royalty_percentage", royalty_percentage)
+            .set_metadata(
+                collection,
+                "royalty_badge",
+                GlobalAddress::from(royalty_badge),
+            );
+        self.env.new_instruction("set_royalty_metadata", 3, 2);
+        self.execute_expect_success(false);
+        collection
+    }
+
+    // Mints a fresh fungible into the test account, to quote a book in something other than XRD
+    pub fn create_quote_resource(&mut self, divisibility: u8) -> ResourceAddress {
+        self.env
+            .test_runner
+            .create_fungible_resource(dec!(1000000), divisibility, self.env.account)
+    }
+
+    pub fn quote_address(&self) -> ResourceAddress {
+        self.quote_address
+    }
+
+    pub fn u_address(&self) -> ResourceAddress {
+        self.env.u_address
+    }
+
+    pub fn j_nft_address(&self) -> ResourceAddress {
+        self.env.j_nft_address
+    }
+
+    pub fn k_nft_address(&self) -> ResourceAddress {
+        self.env.k_nft_address
+    }
 }
 
 pub fn instantiate_expect_success(x_address: ResourceAddress) {
@@ -139,9 +873,250 @@ pub fn instantiate_expect_success(x_address: ResourceAddress) {
     helper.instantiate_default(x_address, true);
 }
 
-pub fn instantiate_expect_failure(x_address: ResourceAddress) {
+pub fn instantiate_expect_failure(x_address: ResourceAddress, quote_address: ResourceAddress) {
     let mut helper = HelloSwapTestHelper::new();
-    helper.instantiate(x_address).execute_expect_failure(true);
+    helper
+        .instantiate(x_address, quote_address, None)
+        .execute_expect_failure(true);
+}
+
+// A bidbook for the j_nft collection quoted in XRD, with the default limits and no owner badge
+pub fn new_bidbook() -> HelloSwapTestHelper {
+    let mut helper = HelloSwapTestHelper::new();
+    let collection = helper.j_nft_address();
+    helper.instantiate_default(collection, false);
+    helper
+}
+
+pub fn place_bid_expect_receipt(
+    helper: &mut HelloSwapTestHelper,
+    qty: u64,
+    price_per_nft: Decimal,
+) -> NonFungibleGlobalId {
+    let receipt = helper
+        .place_bid(price_per_nft * qty, qty, price_per_nft)
+        .execute_expect_success(false);
+    let output_buckets = receipt.output_buckets("place_bid");
+
+    receipt_in(&output_buckets[0], helper.j_nft_address())
+}
+
+pub fn place_ask_expect_receipt(
+    helper: &mut HelloSwapTestHelper,
+    qty: u64,
+    price_per_nft: Decimal,
+) -> NonFungibleGlobalId {
+    let receipt = helper
+        .place_ask(Decimal::from(qty), price_per_nft)
+        .execute_expect_success(false);
+    let output_buckets = receipt.output_buckets("place_ask");
+
+    receipt_in(&output_buckets[0], helper.j_nft_address())
+}
+
+// Picks the order receipt out of returned buckets - NFTs of the collection are fills, not receipts
+pub fn receipt_in(
+    buckets: &Vec<ResourceSpecifier>,
+    collection: ResourceAddress,
+) -> NonFungibleGlobalId {
+    buckets
+        .iter()
+        .find_map(|bucket| match bucket {
+            Ids(receipt_address, local_ids) if *receipt_address != collection => Some(
+                NonFungibleGlobalId::new(*receipt_address, local_ids.first().unwrap().clone()),
+            ),
+            _ => None,
+        })
+        .unwrap()
+}
+
+// Sums a resource over the buckets returned by one instruction - NFTs count one each
+pub fn bucket_amount(
+    buckets: &Vec<ResourceSpecifier>,
+    resource_address: ResourceAddress,
+) -> Decimal {
+    buckets
+        .iter()
+        .map(|bucket| match bucket {
+            Amount(address, amount) if *address == resource_address => *amount,
+            Ids(address, local_ids) if *address == resource_address => {
+                Decimal::from(local_ids.len() as u64)
+            }
+            _ => dec!(0),
+        })
+        .fold(dec!(0), |total, amount| total + amount)
+}
+
+pub fn fetch_order(
+    helper: &mut HelloSwapTestHelper,
+    order_id: NonFungibleGlobalId,
+) -> Option<Order> {
+    let receipt = helper.get_order(order_id).execute_expect_success(false);
+    receipt.outputs::<Option<Order>>("get_order")[0].clone()
+}
+
+pub fn bidbook_expect(
+    helper: &mut HelloSwapTestHelper,
+    x_address: ResourceAddress,
+    quote_address: ResourceAddress,
+) -> Option<ComponentAddress> {
+    let receipt = helper
+        .get_bidbook(x_address, quote_address)
+        .execute_expect_success(false);
+    receipt.outputs::<Option<ComponentAddress>>("get_bidbook")[0]
+}
+
+pub fn bidbooks_expect(
+    helper: &mut HelloSwapTestHelper,
+    start: u64,
+    limit: u64,
+    pairs_expected: Vec<(ResourceAddress, ResourceAddress)>,
+) {
+    let receipt = helper
+        .get_bidbooks(start, limit)
+        .execute_expect_success(false);
+    let entries = receipt.outputs::<Vec<BidbookEntry>>("get_bidbooks")[0].clone();
+    let pairs: Vec<(ResourceAddress, ResourceAddress)> = entries
+        .into_iter()
+        .map(|entry| (entry.collection, entry.quote_resource))
+        .collect();
+
+    assert_eq!(pairs, pairs_expected);
+}
+
+// Returns the bidbook addresses listed for the collection, in the order their quote resources were added
+pub fn collection_bidbooks_expect(
+    helper: &mut HelloSwapTestHelper,
+    x_address: ResourceAddress,
+    quotes_expected: Vec<ResourceAddress>,
+) -> Vec<ComponentAddress> {
+    let receipt = helper
+        .get_collection_bidbooks(x_address)
+        .execute_expect_success(false);
+    let entries = receipt.outputs::<Vec<BidbookEntry>>("get_collection_bidbooks")[0].clone();
+    let quotes: Vec<ResourceAddress> = entries
+        .iter()
+        .map(|entry| {
+            assert_eq!(entry.collection, x_address);
+            entry.quote_resource
+        })
+        .collect();
+
+    assert_eq!(quotes, quotes_expected);
+
+    entries.into_iter().map(|entry| entry.bidbook).collect()
+}
+
+pub fn bidbook_count_expect(helper: &mut HelloSwapTestHelper, count_expected: u64) {
+    let receipt = helper.get_bidbook_count().execute_expect_success(false);
+    let count: u64 = receipt.outputs("get_bidbook_count")[0];
+
+    assert_eq!(count, count_expected);
+}
+
+pub fn level_orders_expect(
+    helper: &mut HelloSwapTestHelper,
+    price: Decimal,
+    cursor: Option<NonFungibleGlobalId>,
+    limit: u32,
+    order_ids_expected: Vec<NonFungibleGlobalId>,
+) {
+    let receipt = helper
+        .get_orders_at_level(price, cursor, limit)
+        .execute_expect_success(false);
+    let orders = receipt.outputs::<Vec<Order>>("get_orders_at_level")[0].clone();
+    let order_ids: Vec<NonFungibleGlobalId> =
+        orders.into_iter().map(|order| order.order_id).collect();
+
+    assert_eq!(order_ids, order_ids_expected);
+}
+
+pub fn queue_position_expect(
+    helper: &mut HelloSwapTestHelper,
+    order_id: NonFungibleGlobalId,
+    position_expected: Option<u64>,
+) {
+    let receipt = helper
+        .get_queue_position(order_id)
+        .execute_expect_success(false);
+    let position: Option<u64> = receipt.outputs("get_queue_position")[0];
+
+    assert_eq!(position, position_expected);
+}
+
+pub fn depth_expect(
+    helper: &mut HelloSwapTestHelper,
+    depth_expected: Vec<(Decimal, u64, Decimal)>,
+) {
+    depth_levels_expect(helper, u32::MAX, depth_expected);
+}
+
+pub fn depth_levels_expect(
+    helper: &mut HelloSwapTestHelper,
+    levels: u32,
+    depth_expected: Vec<(Decimal, u64, Decimal)>,
+) {
+    let receipt = helper.get_depth(levels).execute_expect_success(false);
+    let depth = receipt.outputs::<Vec<DepthLevel>>("get_depth")[0].clone();
+    let depth: Vec<(Decimal, u64, Decimal)> = depth
+        .into_iter()
+        .map(|level| (level.level_price, level.order_count, level.level_total))
+        .collect();
+
+    assert_eq!(depth, depth_expected);
+}
+
+pub fn ask_depth_expect(
+    helper: &mut HelloSwapTestHelper,
+    depth_expected: Vec<(Decimal, u64, Decimal)>,
+) {
+    let receipt = helper.get_ask_depth(u32::MAX).execute_expect_success(false);
+    let depth = receipt.outputs::<Vec<DepthLevel>>("get_ask_depth")[0].clone();
+    let depth: Vec<(Decimal, u64, Decimal)> = depth
+        .into_iter()
+        .map(|level| (level.level_price, level.order_count, level.level_total))
+        .collect();
+
+    assert_eq!(depth, depth_expected);
+}
+
+pub fn best_bid_expect(helper: &mut HelloSwapTestHelper, best_bid_expected: Option<Decimal>) {
+    let receipt = helper.best_bid().execute_expect_success(false);
+    let best_bid: Option<Decimal> = receipt.outputs("best_bid")[0];
+
+    assert_eq!(best_bid, best_bid_expected);
+}
+
+pub fn best_ask_expect(helper: &mut HelloSwapTestHelper, best_ask_expected: Option<Decimal>) {
+    let receipt = helper.best_ask().execute_expect_success(false);
+    let best_ask: Option<Decimal> = receipt.outputs("best_ask")[0];
+
+    assert_eq!(best_ask, best_ask_expected);
+}
+
+pub fn spread_expect(helper: &mut HelloSwapTestHelper, spread_expected: Option<Decimal>) {
+    let receipt = helper.spread().execute_expect_success(false);
+    let spread: Option<Decimal> = receipt.outputs("spread")[0];
+
+    assert_eq!(spread, spread_expected);
+}
+
+pub fn fill_bid_expect_payout(
+    helper: &mut HelloSwapTestHelper,
+    nft_amount: Decimal,
+    payout_expected: Decimal,
+) {
+    let nft_address = helper.j_nft_address();
+    let quote_address = helper.quote_address();
+    let receipt = helper
+        .fill_bid(nft_address, nft_amount)
+        .execute_expect_success(true);
+    let output_buckets = receipt.output_buckets("fill_bid");
+
+    assert_eq!(
+        output_buckets,
+        vec![vec![Amount(quote_address, payout_expected)]]
+    );
 }
 
 // pub fn swap_expect_success(
@@ -0,0 +1,240 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn owned_bidbook() -> (HelloSwapTestHelper, ResourceAddress) {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        (helper, owner_badge)
+    }
+
+    #[test]
+    fn test_pause_with_owner_badge() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .admin_call("unpause", Some(owner_badge))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_pause_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .admin_call("pause", None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_unpause_without_owner_badge() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .admin_call("unpause", None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_lock_settings_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .admin_call("lock_settings", None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_set_book_metadata_with_owner_badge() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .set_book_metadata("name", "Bidbook", Some(owner_badge))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_set_book_metadata_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .set_book_metadata("name", "Bidbook", None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_set_book_metadata_after_lock() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("lock_settings", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .set_book_metadata("name", "Bidbook", Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_set_order_limits_with_owner_badge() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .set_order_limits(dec!(1), dec!(1), 1, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(10.5), 1, dec!(10.5))
+            .execute_expect_failure(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_set_order_limits_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .set_order_limits(dec!(1), dec!(1), 1, None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_set_order_limits_after_lock() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("lock_settings", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .set_order_limits(dec!(1), dec!(1), 1, Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_set_fee_schedule_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .set_fee_schedule(0, 100, None)
+            .execute_expect_failure(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+    }
+
+    #[test]
+    fn test_set_fee_schedule_after_lock() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("lock_settings", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .set_fee_schedule(0, 100, Some(owner_badge))
+            .execute_expect_failure(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+    }
+
+    #[test]
+    fn test_place_bid_while_paused() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_unpause_after_close() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        helper
+            .admin_call("close_book", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .admin_call("unpause", Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_cancel_bid_while_paused() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .cancel_bid(order_id.resource_address())
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_claim_while_paused() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .claim(order_id.resource_address(), dec!(1))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_cancel_bid_after_close() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        helper
+            .admin_call("close_book", Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .cancel_bid(order_id.resource_address())
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_emergency_withdraw_open_and_filled_receipts() {
+        let (mut helper, owner_badge) = owned_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let receipt_address = order_id.resource_address();
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        helper
+            .admin_call("pause", Some(owner_badge))
+            .execute_expect_success(false);
+
+        // the filled bid hands over its NFT, the open bid is cancelled and refunded
+        let receipt = helper
+            .emergency_withdraw(receipt_address, dec!(2))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("emergency_withdraw");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(10));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+
+    #[test]
+    fn test_emergency_withdraw_while_trading() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        helper
+            .emergency_withdraw(order_id.resource_address(), dec!(1))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_close_book_without_owner_badge() {
+        let (mut helper, _owner_badge) = owned_bidbook();
+        helper
+            .admin_call("close_book", None)
+            .execute_expect_failure(false);
+    }
+}
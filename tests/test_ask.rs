@@ -0,0 +1,81 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_place_ask_lists_nfts() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 2, dec!(10));
+        ask_depth_expect(&mut helper, vec![(dec!(10), 1, dec!(20))]);
+    }
+
+    #[test]
+    fn test_fill_ask_returns_nfts_and_change() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 2, dec!(10));
+
+        let receipt = helper.fill_ask(dec!(25), 2).execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("fill_ask");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(2));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(5));
+        ask_depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_cancel_ask_after_partial_fill() {
+        let mut helper = new_bidbook();
+        let order_id = place_ask_expect_receipt(&mut helper, 2, dec!(10));
+        let receipt_address = order_id.resource_address();
+        helper.fill_ask(dec!(10), 1).execute_expect_success(false);
+
+        let receipt = helper
+            .cancel_ask(receipt_address)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("cancel_ask");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(10));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+        ask_depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_claim_ask_proceeds() {
+        let mut helper = new_bidbook();
+        let order_id = place_ask_expect_receipt(&mut helper, 2, dec!(10));
+        let receipt_address = order_id.resource_address();
+        helper.fill_ask(dec!(20), 2).execute_expect_success(false);
+
+        let receipt = helper
+            .claim_ask(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_ask");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(20));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+
+    #[test]
+    fn test_ask_level_total_tracks_listed_value() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 3, dec!(10));
+        place_ask_expect_receipt(&mut helper, 2, dec!(10));
+        ask_depth_expect(&mut helper, vec![(dec!(10), 2, dec!(50))]);
+
+        helper.fill_ask(dec!(10), 1).execute_expect_success(false);
+        ask_depth_expect(&mut helper, vec![(dec!(10), 2, dec!(40))]);
+
+        helper.fill_ask(dec!(30), 3).execute_expect_success(false);
+        ask_depth_expect(&mut helper, vec![(dec!(10), 1, dec!(10))]);
+
+        helper.fill_ask(dec!(10), 1).execute_expect_success(false);
+        ask_depth_expect(&mut helper, vec![]);
+    }
+}
@@ -0,0 +1,93 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_best_bid_empty_book() {
+        let mut helper = new_bidbook();
+        best_bid_expect(&mut helper, None);
+    }
+
+    #[test]
+    fn test_best_bid_single_level() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        best_bid_expect(&mut helper, Some(dec!(10)));
+    }
+
+    #[test]
+    fn test_best_bid_multi_level() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        place_bid_expect_receipt(&mut helper, 2, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(15));
+        best_bid_expect(&mut helper, Some(dec!(20)));
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(20));
+        best_bid_expect(&mut helper, Some(dec!(20)));
+    }
+
+    #[test]
+    fn test_fill_bid_walks_all_levels() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(30));
+    }
+
+    #[test]
+    fn test_best_bid_emptied_by_cancel() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        helper
+            .cancel_bid(order_id.resource_address())
+            .execute_expect_success(false);
+        best_bid_expect(&mut helper, None);
+
+        let nft_address = helper.j_nft_address();
+        helper
+            .fill_bid(nft_address, dec!(1))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_best_bid_after_full_fill() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(20));
+        best_bid_expect(&mut helper, Some(dec!(10)));
+        depth_expect(&mut helper, vec![(dec!(10), 1, dec!(10))]);
+    }
+
+    #[test]
+    fn test_best_bid_emptied_by_fill() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(30));
+        best_bid_expect(&mut helper, None);
+        depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_level_aggregates_on_fills() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        depth_expect(&mut helper, vec![(dec!(10), 2, dec!(30))]);
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        depth_expect(&mut helper, vec![(dec!(10), 2, dec!(20))]);
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        depth_expect(&mut helper, vec![(dec!(10), 1, dec!(10))]);
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        depth_expect(&mut helper, vec![]);
+    }
+}
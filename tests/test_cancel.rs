@@ -0,0 +1,115 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cancel_open_bid_refunds_in_full() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 3, dec!(10));
+        let receipt_address = order_id.resource_address();
+
+        let receipt = helper
+            .cancel_bid(receipt_address)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("cancel_bid");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(30));
+        assert_eq!(bucket_amount(&output_buckets[0], receipt_address), dec!(0));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+
+    #[test]
+    fn test_cancel_bid_after_partial_fill() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 3, dec!(10));
+        let receipt_address = order_id.resource_address();
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+
+        let receipt = helper
+            .cancel_bid(receipt_address)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("cancel_bid");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(20));
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+
+    #[test]
+    fn test_cancel_bid_with_foreign_receipt() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let nft_address = helper.j_nft_address();
+        helper.cancel_bid(nft_address).execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_cancel_from_head_middle_and_tail_keeps_fifo() {
+        let mut helper = new_bidbook();
+        let first = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let third = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let fourth = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        helper
+            .cancel_bid_by_id(&second)
+            .execute_expect_success(false);
+        level_orders_expect(
+            &mut helper,
+            dec!(10),
+            None,
+            10,
+            vec![first.clone(), third.clone(), fourth.clone()],
+        );
+
+        helper
+            .cancel_bid_by_id(&first)
+            .execute_expect_success(false);
+        level_orders_expect(
+            &mut helper,
+            dec!(10),
+            None,
+            10,
+            vec![third.clone(), fourth.clone()],
+        );
+
+        helper
+            .cancel_bid_by_id(&fourth)
+            .execute_expect_success(false);
+        level_orders_expect(&mut helper, dec!(10), None, 10, vec![third.clone()]);
+
+        let fifth = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        level_orders_expect(
+            &mut helper,
+            dec!(10),
+            None,
+            10,
+            vec![third.clone(), fifth.clone()],
+        );
+        depth_expect(&mut helper, vec![(dec!(10), 2, dec!(20))]);
+
+        // the oldest remaining bid is filled first
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        level_orders_expect(&mut helper, dec!(10), None, 10, vec![fifth]);
+    }
+
+    #[test]
+    fn test_fill_bid_walks_past_cancelled_level() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(30));
+        let middle = place_bid_expect_receipt(&mut helper, 1, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        helper
+            .cancel_bid_by_id(&middle)
+            .execute_expect_success(false);
+
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(40));
+        best_bid_expect(&mut helper, None);
+    }
+}
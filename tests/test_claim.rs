@@ -0,0 +1,86 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_claim_filled_bid() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        let receipt_address = order_id.resource_address();
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(20));
+
+        let receipt = helper
+            .claim(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(2));
+        assert_eq!(bucket_amount(&output_buckets[0], receipt_address), dec!(0));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+
+    #[test]
+    fn test_claim_partially_filled_bid_keeps_receipt() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        let receipt_address = order_id.resource_address();
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+
+        let receipt = helper
+            .claim(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], receipt_address), dec!(1));
+        assert_eq!(helper.balance(receipt_address), dec!(1));
+    }
+
+    #[test]
+    fn test_claim_with_foreign_receipt() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let nft_address = helper.j_nft_address();
+        helper
+            .claim(nft_address, dec!(1))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_claim_filled_and_open_receipts_in_one_call() {
+        let mut helper = new_bidbook();
+        let filled = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        let partial = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        let receipt_address = filled.resource_address();
+        fill_bid_expect_payout(&mut helper, dec!(3), dec!(30));
+
+        // 2 NFTs for the filled order and 1 for the partially filled one, which keeps its receipt
+        let receipt = helper
+            .claim(receipt_address, dec!(2))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(3));
+        assert_eq!(bucket_amount(&output_buckets[0], receipt_address), dec!(1));
+        assert_eq!(helper.balance(receipt_address), dec!(1));
+        assert_eq!(fetch_order(&mut helper, filled), None);
+        assert!(fetch_order(&mut helper, partial).is_some());
+
+        // the receipt handed back is the open order's - once it fills, it claims the last NFT and is burned
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        let receipt = helper
+            .claim(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim");
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+}
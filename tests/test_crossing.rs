@@ -0,0 +1,113 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_place_bid_crossing_ask_returns_fills_and_change() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 1, dec!(8));
+
+        let receipt = helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+        let nft_address = helper.j_nft_address();
+
+        // the whole bid executes at the ask price, so no receipt is minted
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(2));
+        ask_depth_expect(&mut helper, vec![]);
+        depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_place_bid_partly_crossing_rests_remainder() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 1, dec!(10));
+
+        let receipt = helper
+            .place_bid(dec!(30), 3, dec!(10))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+        let nft_address = helper.j_nft_address();
+        let order_id = receipt_in(&output_buckets[0], nft_address);
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(0));
+
+        let order = fetch_order(&mut helper, order_id).unwrap();
+        assert_eq!(order.order_qty, 2);
+        assert_eq!(order.remaining_qty, 2);
+        depth_expect(&mut helper, vec![(dec!(10), 1, dec!(20))]);
+        ask_depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_place_ask_sells_into_bids() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        place_bid_expect_receipt(&mut helper, 1, dec!(12));
+
+        let receipt = helper
+            .place_ask(dec!(3), dec!(9))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_ask");
+
+        // each NFT sells at the bid price, the unsold one is listed at the ask price
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(22));
+        depth_expect(&mut helper, vec![]);
+        ask_depth_expect(&mut helper, vec![(dec!(9), 1, dec!(9))]);
+    }
+
+    #[test]
+    fn test_place_bid_walks_asks_in_price_time_order() {
+        let mut helper = new_bidbook();
+        let expensive = place_ask_expect_receipt(&mut helper, 1, dec!(12));
+        let first = place_ask_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_ask_expect_receipt(&mut helper, 1, dec!(10));
+
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        assert!(fetch_order(&mut helper, first).is_none());
+        assert!(fetch_order(&mut helper, second.clone()).is_some());
+        assert!(fetch_order(&mut helper, expensive.clone()).is_some());
+
+        let receipt = helper
+            .place_bid(dec!(24), 2, dec!(12))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(2));
+        assert!(fetch_order(&mut helper, second).is_none());
+        assert!(fetch_order(&mut helper, expensive).is_none());
+        ask_depth_expect(&mut helper, vec![]);
+    }
+
+    #[test]
+    fn test_remainder_below_min_order_qty_not_rested() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_order_limits(dec!(0.1), dec!(1), 2, Some(owner_badge))
+            .execute_expect_success(false);
+        place_ask_expect_receipt(&mut helper, 1, dec!(10));
+
+        // one NFT is bought, the single NFT left is below the minimum qty and is not rested
+        let receipt = helper
+            .place_bid(dec!(20), 2, dec!(10))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+        let nft_address = helper.j_nft_address();
+
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(10));
+        depth_expect(&mut helper, vec![]);
+    }
+}
@@ -0,0 +1,85 @@
+mod helper;
+use hello_swap::hello_swap::{
+    BidCancelled, BidFilled, BidPartiallyFilled, BidPlaced, NftsClaimed, PriceLevelCreated,
+    PriceLevelRemoved,
+};
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_place_bid_events() {
+        let mut helper = new_bidbook();
+
+        let receipt = helper
+            .place_bid(dec!(20), 2, dec!(10))
+            .execute_expect_success(false);
+        let placed: Vec<BidPlaced> = helper.events(&receipt, "BidPlaced");
+        let created: Vec<PriceLevelCreated> = helper.events(&receipt, "PriceLevelCreated");
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].order_price, dec!(10));
+        assert_eq!(placed[0].order_qty, 2);
+        assert_eq!(placed[0].order_total, dec!(20));
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].level_price, dec!(10));
+
+        // joining an existing level does not create it again
+        let receipt = helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        let created: Vec<PriceLevelCreated> = helper.events(&receipt, "PriceLevelCreated");
+        assert_eq!(created.len(), 0);
+    }
+
+    #[test]
+    fn test_fill_bid_events() {
+        let mut helper = new_bidbook();
+        let nft_address = helper.j_nft_address();
+        place_bid_expect_receipt(&mut helper, 3, dec!(10));
+
+        let receipt = helper
+            .fill_bid(nft_address, dec!(1))
+            .execute_expect_success(false);
+        let partial: Vec<BidPartiallyFilled> = helper.events(&receipt, "BidPartiallyFilled");
+        let removed: Vec<PriceLevelRemoved> = helper.events(&receipt, "PriceLevelRemoved");
+        assert_eq!(partial.len(), 1);
+        assert_eq!(partial[0].fill_value, dec!(10));
+        assert_eq!(partial[0].filled_qty, 1);
+        assert_eq!(partial[0].remaining_qty, 2);
+        assert_eq!(removed.len(), 0);
+
+        let receipt = helper
+            .fill_bid(nft_address, dec!(2))
+            .execute_expect_success(false);
+        let filled: Vec<BidFilled> = helper.events(&receipt, "BidFilled");
+        let removed: Vec<PriceLevelRemoved> = helper.events(&receipt, "PriceLevelRemoved");
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].fill_qty, 2);
+        assert_eq!(filled[0].fill_value, dec!(20));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].level_price, dec!(10));
+    }
+
+    #[test]
+    fn test_cancel_bid_events() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 3, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+
+        let receipt = helper
+            .cancel_bid(order_id.resource_address())
+            .execute_expect_success(false);
+        let cancelled: Vec<BidCancelled> = helper.events(&receipt, "BidCancelled");
+        let claimed: Vec<NftsClaimed> = helper.events(&receipt, "NftsClaimed");
+        let removed: Vec<PriceLevelRemoved> = helper.events(&receipt, "PriceLevelRemoved");
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].order_id, order_id);
+        assert_eq!(cancelled[0].refunded_amount, dec!(20));
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].nft_qty, 1);
+        assert_eq!(removed.len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const START: i64 = 1_700_000_000;
+    const HOUR: i64 = 3_600;
+
+    fn bidbook_at_start() -> HelloSwapTestHelper {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.advance_clock_to(START);
+        helper.instantiate_default(collection, false);
+        helper
+    }
+
+    #[test]
+    fn test_place_bid_expiry_in_past() {
+        let mut helper = bidbook_at_start();
+        helper
+            .place_bid_until(dec!(10), 1, dec!(10), Some(START - HOUR))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_fill_bid_before_expiry() {
+        let mut helper = bidbook_at_start();
+        helper
+            .place_bid_until(dec!(20), 1, dec!(20), Some(START + HOUR))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        helper.advance_clock_to(START + HOUR / 2);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(20));
+    }
+
+    #[test]
+    fn test_fill_bid_skips_expired_bid() {
+        let mut helper = bidbook_at_start();
+        helper
+            .place_bid_until(dec!(20), 1, dec!(20), Some(START + HOUR))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        helper.advance_clock_to(START + 2 * HOUR);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+    }
+
+    fn place_bid_until_expect_receipt(
+        helper: &mut HelloSwapTestHelper,
+        price_per_nft: Decimal,
+        expires_at: i64,
+    ) -> NonFungibleGlobalId {
+        let receipt = helper
+            .place_bid_until(price_per_nft, 1, price_per_nft, Some(expires_at))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+
+        receipt_in(&output_buckets[0], helper.j_nft_address())
+    }
+
+    #[test]
+    fn test_retire_expired_bids_clears_best_bid_and_depth() {
+        let mut helper = bidbook_at_start();
+        let order_id = place_bid_until_expect_receipt(&mut helper, dec!(20), START + HOUR);
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        helper.advance_clock_to(START + 2 * HOUR);
+        best_bid_expect(&mut helper, Some(dec!(20)));
+
+        helper
+            .retire_expired_bids(vec![order_id])
+            .execute_expect_success(false);
+
+        best_bid_expect(&mut helper, Some(dec!(10)));
+        depth_expect(&mut helper, vec![(dec!(10), 1, dec!(10))]);
+    }
+
+    #[test]
+    fn test_retire_expired_bids_skips_live_bids() {
+        let mut helper = bidbook_at_start();
+        let order_id = place_bid_until_expect_receipt(&mut helper, dec!(20), START + 2 * HOUR);
+        helper.advance_clock_to(START + HOUR);
+
+        helper
+            .retire_expired_bids(vec![order_id])
+            .execute_expect_success(false);
+
+        best_bid_expect(&mut helper, Some(dec!(20)));
+        depth_expect(&mut helper, vec![(dec!(20), 1, dec!(20))]);
+    }
+
+    #[test]
+    fn test_claim_refund_of_retired_bid() {
+        let mut helper = bidbook_at_start();
+        let order_id = place_bid_until_expect_receipt(&mut helper, dec!(20), START + HOUR);
+        let receipt_address = order_id.resource_address();
+        helper.advance_clock_to(START + 2 * HOUR);
+        helper
+            .retire_expired_bids(vec![order_id])
+            .execute_expect_success(false);
+        best_bid_expect(&mut helper, None);
+
+        let receipt = helper
+            .claim(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(20));
+        assert_eq!(bucket_amount(&output_buckets[0], receipt_address), dec!(0));
+        assert_eq!(helper.balance(receipt_address), dec!(0));
+    }
+}
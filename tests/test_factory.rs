@@ -0,0 +1,143 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn factory() -> (HelloSwapTestHelper, ResourceAddress) {
+        let mut helper = HelloSwapTestHelper::new();
+        let owner_badge = helper.u_address();
+        helper.instantiate_factory(owner_badge, false);
+        (helper, owner_badge)
+    }
+
+    #[test]
+    fn test_create_bidbook() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+
+        assert!(bidbook_expect(&mut helper, collection, XRD).is_some());
+
+        let other_collection = helper.k_nft_address();
+        assert!(bidbook_expect(&mut helper, other_collection, XRD).is_none());
+    }
+
+    #[test]
+    fn test_create_bidbook_without_factory_badge() {
+        let (mut helper, _owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        helper
+            .create_bidbook(collection, XRD, None)
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_create_duplicate_bidbook() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_get_bidbooks_in_creation_order() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        let other_collection = helper.k_nft_address();
+        let other_quote = helper.x_address();
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(collection, other_quote, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(other_collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+
+        bidbooks_expect(
+            &mut helper,
+            0,
+            2,
+            vec![(collection, XRD), (collection, other_quote)],
+        );
+        bidbooks_expect(&mut helper, 2, 10, vec![(other_collection, XRD)]);
+        bidbooks_expect(&mut helper, 5, 1, vec![]);
+    }
+
+    #[test]
+    fn test_get_collection_bidbooks_with_two_quotes() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        let other_collection = helper.k_nft_address();
+        let other_quote = helper.x_address();
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(other_collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(collection, other_quote, Some(owner_badge))
+            .execute_expect_success(false);
+
+        let bidbooks = collection_bidbooks_expect(&mut helper, collection, vec![XRD, other_quote]);
+        assert_eq!(
+            Some(bidbooks[0]),
+            bidbook_expect(&mut helper, collection, XRD)
+        );
+        assert_eq!(
+            Some(bidbooks[1]),
+            bidbook_expect(&mut helper, collection, other_quote)
+        );
+
+        let bidbooks = collection_bidbooks_expect(&mut helper, other_collection, vec![XRD]);
+        assert_eq!(
+            Some(bidbooks[0]),
+            bidbook_expect(&mut helper, other_collection, XRD)
+        );
+    }
+
+    #[test]
+    fn test_get_collection_bidbooks_unknown_collection() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        let other_collection = helper.k_nft_address();
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+
+        collection_bidbooks_expect(&mut helper, other_collection, vec![]);
+    }
+
+    #[test]
+    fn test_get_bidbook_count() {
+        let (mut helper, owner_badge) = factory();
+        let collection = helper.j_nft_address();
+        let other_quote = helper.x_address();
+        bidbook_count_expect(&mut helper, 0);
+
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .create_bidbook(collection, other_quote, Some(owner_badge))
+            .execute_expect_success(false);
+        bidbook_count_expect(&mut helper, 2);
+
+        // a refused duplicate is not counted
+        helper
+            .create_bidbook(collection, XRD, Some(owner_badge))
+            .execute_expect_failure(false);
+        bidbook_count_expect(&mut helper, 2);
+    }
+}
@@ -0,0 +1,309 @@
+mod helper;
+use hello_swap::hello_swap::{MakerRebatePaid, OrderStatus};
+use helper::*;
+use radix_engine::system::system_modules::execution_trace::ResourceSpecifier::Amount;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bidbook_with_bid(qty: u64, order_price: Decimal) -> HelloSwapTestHelper {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        helper
+            .place_bid(order_price * qty, qty, order_price)
+            .execute_expect_success(false);
+        helper
+    }
+
+    #[test]
+    fn test_fill_bid_full() {
+        let mut helper = bidbook_with_bid(3, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(3), dec!(30));
+    }
+
+    #[test]
+    fn test_fill_bid_partial_pays_for_filled_qty() {
+        let mut helper = bidbook_with_bid(3, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+    }
+
+    #[test]
+    fn test_fill_bid_partial_then_full() {
+        let mut helper = bidbook_with_bid(3, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(20));
+    }
+
+    #[test]
+    fn test_fill_bid_multiple_partial_fills() {
+        let mut helper = bidbook_with_bid(4, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(20));
+    }
+
+    #[test]
+    fn test_receipt_tracks_fills() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        let order_id = place_bid_expect_receipt(&mut helper, 3, dec!(10));
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(10));
+        let receipt = helper.receipt_data(&order_id);
+        assert_eq!(receipt.order_status, OrderStatus::PARTIAL);
+        assert_eq!(receipt.filled_qty, 1);
+        assert_eq!(receipt.remaining_qty, 2);
+        assert_eq!(receipt.avg_fill_price, dec!(10));
+
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(20));
+        let receipt = helper.receipt_data(&order_id);
+        assert_eq!(receipt.order_status, OrderStatus::FILLED);
+        assert_eq!(receipt.filled_qty, 3);
+        assert_eq!(receipt.remaining_qty, 0);
+        assert_eq!(receipt.avg_fill_price, dec!(10));
+    }
+
+    #[test]
+    fn test_place_bid_with_change() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        let receipt = helper
+            .place_bid(dec!(35.5), 3, dec!(10))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("place_bid");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(5.5));
+        fill_bid_expect_payout(&mut helper, dec!(3), dec!(30));
+    }
+
+    #[test]
+    fn test_place_bid_not_covering_qty() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        helper
+            .place_bid(dec!(29), 3, dec!(10))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_place_bid_off_tick() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        helper
+            .place_bid(dec!(10.05), 1, dec!(10.05))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_place_bid_below_min_order_value() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+        helper
+            .place_bid(dec!(0.5), 1, dec!(0.5))
+            .execute_expect_failure(false);
+    }
+
+    fn bidbook_with_two_levels() -> HelloSwapTestHelper {
+        let mut helper = bidbook_with_bid(1, dec!(20));
+        helper
+            .place_bid(dec!(10), 1, dec!(10))
+            .execute_expect_success(false);
+        helper
+    }
+
+    #[test]
+    fn test_fill_bid_min_total_proceeds_met() {
+        let mut helper = bidbook_with_two_levels();
+        let nft_address = helper.j_nft_address();
+        helper
+            .fill_bid_with_limits(nft_address, dec!(2), dec!(0), Some(dec!(30)))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_fill_bid_stops_at_min_price() {
+        let mut helper = bidbook_with_two_levels();
+        let nft_address = helper.j_nft_address();
+        let receipt = helper
+            .fill_bid_with_limits(nft_address, dec!(2), dec!(15), Some(dec!(20)))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("fill_bid");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(20));
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        depth_expect(&mut helper, vec![(dec!(10), 1, dec!(10))]);
+    }
+
+    #[test]
+    fn test_fill_bid_min_price_and_total_proceeds_not_met() {
+        let mut helper = bidbook_with_two_levels();
+        let nft_address = helper.j_nft_address();
+        helper
+            .fill_bid_with_limits(nft_address, dec!(2), dec!(15), Some(dec!(30)))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_fill_bid_immediate_or_cancel_returns_unsold() {
+        let mut helper = bidbook_with_bid(2, dec!(10));
+        let nft_address = helper.j_nft_address();
+        let receipt = helper
+            .fill_bid(nft_address, dec!(3))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("fill_bid");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(20));
+        assert_eq!(bucket_amount(&output_buckets[0], nft_address), dec!(1));
+        best_bid_expect(&mut helper, None);
+    }
+
+    #[test]
+    fn test_fill_bid_exact_all_matched() {
+        let mut helper = bidbook_with_bid(2, dec!(10));
+        let nft_address = helper.j_nft_address();
+        helper
+            .fill_bid_exact(nft_address, dec!(2))
+            .execute_expect_success(false);
+    }
+
+    #[test]
+    fn test_fill_bid_exact_not_all_matched() {
+        let mut helper = bidbook_with_bid(2, dec!(10));
+        let nft_address = helper.j_nft_address();
+        helper
+            .fill_bid_exact(nft_address, dec!(3))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_fill_bid_net_of_fee() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(0, 100, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(99));
+    }
+
+    #[test]
+    fn test_set_fee_above_max() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(0, MAX_FEE_BPS + 1, Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+
+    #[test]
+    fn test_place_bid_escrows_maker_fee() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(100, 0, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(101), 1, dec!(100))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(100));
+
+        let receipt = helper
+            .admin_call("withdraw_fees", Some(owner_badge))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("withdraw_fees");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(1))]]);
+    }
+
+    #[test]
+    fn test_maker_rebate_limited_to_fee_vault() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        let nft_address = helper.j_nft_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(-100, 100, Some(owner_badge))
+            .execute_expect_success(false);
+
+        // the fee vault is empty on the first fill, so none of the rebate can be paid
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+        let receipt = helper
+            .fill_bid(nft_address, dec!(1))
+            .execute_expect_success(false);
+        let rebates: Vec<MakerRebatePaid> = helper.events(&receipt, "MakerRebatePaid");
+        assert_eq!(rebates.len(), 1);
+        assert_eq!(rebates[0].rebate_due, dec!(1));
+        assert_eq!(rebates[0].rebate_paid, dec!(0));
+
+        // the taker fee of the first fill funds the rebate of the second
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+        let receipt = helper
+            .fill_bid(nft_address, dec!(1))
+            .execute_expect_success(false);
+        let rebates: Vec<MakerRebatePaid> = helper.events(&receipt, "MakerRebatePaid");
+        assert_eq!(rebates.len(), 1);
+        assert_eq!(rebates[0].rebate_due, dec!(1));
+        assert_eq!(rebates[0].rebate_paid, dec!(1));
+    }
+
+    #[test]
+    fn test_depth_excludes_escrowed_maker_fee() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(100, 0, Some(owner_badge))
+            .execute_expect_success(false);
+
+        // 202 is escrowed for 2 NFTs at 100, the level shows the 200 bid at the order price
+        helper
+            .place_bid(dec!(202), 2, dec!(100))
+            .execute_expect_success(false);
+        depth_expect(&mut helper, vec![(dec!(100), 1, dec!(200))]);
+
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(100));
+        depth_expect(&mut helper, vec![(dec!(100), 1, dec!(100))]);
+    }
+
+    #[test]
+    fn test_depth_excludes_maker_rebate() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        helper.instantiate_owned(collection, owner_badge, false);
+        helper
+            .set_fee_schedule(-100, 100, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(300), 3, dec!(100))
+            .execute_expect_success(false);
+
+        // the taker fee of the first fill funds the rebate of the second, which is paid into the order vault
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(99));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(99));
+
+        depth_expect(&mut helper, vec![(dec!(100), 1, dec!(100))]);
+    }
+}
@@ -22,6 +22,15 @@ mod test {
         instantiate_expect_success(res)
     }
 
+    #[test]
+    fn test_instantiate_non_fungible_quote() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper
+            .instantiate(collection, collection, None)
+            .execute_expect_failure(true);
+    }
+
     // #[test]
     // fn test_instantiate_price_zero() {
     //     instantiate_expect_failure(DEC_10, dec!(0));
@@ -0,0 +1,67 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_queue_position_in_placement_order() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+
+        let first = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let third = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        queue_position_expect(&mut helper, first, Some(0));
+        queue_position_expect(&mut helper, second, Some(1));
+        queue_position_expect(&mut helper, third, Some(2));
+    }
+
+    #[test]
+    fn test_fill_bid_oldest_first() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+
+        let first = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+        let third = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        fill_bid_expect_payout(&mut helper, dec!(2), dec!(20));
+
+        queue_position_expect(&mut helper, first, None);
+        queue_position_expect(&mut helper, second, Some(0));
+        queue_position_expect(&mut helper, third, Some(1));
+    }
+
+    #[test]
+    fn test_receipt_queue_position_after_cancel() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        helper.instantiate_default(collection, false);
+
+        let first = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let third = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        assert_eq!(helper.receipt_data(&first).queue_position, 0);
+        assert_eq!(helper.receipt_data(&second).queue_position, 1);
+        assert_eq!(helper.receipt_data(&third).queue_position, 2);
+
+        helper
+            .cancel_bid_by_id(&second)
+            .execute_expect_success(false);
+        let fourth = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+
+        // the receipt keeps the position at placement, the live position moves up
+        assert_eq!(helper.receipt_data(&third).queue_position, 2);
+        queue_position_expect(&mut helper, first, Some(0));
+        queue_position_expect(&mut helper, third, Some(1));
+        assert_eq!(helper.receipt_data(&fourth).queue_position, 2);
+        queue_position_expect(&mut helper, fourth, Some(2));
+    }
+}
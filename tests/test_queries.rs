@@ -0,0 +1,99 @@
+mod helper;
+use hello_swap::hello_swap::Order;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_depth_best_levels_first() {
+        let mut helper = new_bidbook();
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        place_bid_expect_receipt(&mut helper, 2, dec!(20));
+        place_bid_expect_receipt(&mut helper, 1, dec!(15));
+        place_bid_expect_receipt(&mut helper, 1, dec!(15));
+
+        depth_levels_expect(
+            &mut helper,
+            2,
+            vec![(dec!(20), 1, dec!(40)), (dec!(15), 2, dec!(30))],
+        );
+    }
+
+    #[test]
+    fn test_get_ask_depth_best_levels_first() {
+        let mut helper = new_bidbook();
+        place_ask_expect_receipt(&mut helper, 1, dec!(14));
+        place_ask_expect_receipt(&mut helper, 2, dec!(12));
+
+        ask_depth_expect(
+            &mut helper,
+            vec![(dec!(12), 1, dec!(24)), (dec!(14), 1, dec!(14))],
+        );
+    }
+
+    #[test]
+    fn test_best_ask_and_spread() {
+        let mut helper = new_bidbook();
+        best_ask_expect(&mut helper, None);
+        spread_expect(&mut helper, None);
+
+        place_ask_expect_receipt(&mut helper, 1, dec!(14));
+        place_ask_expect_receipt(&mut helper, 1, dec!(12));
+        best_ask_expect(&mut helper, Some(dec!(12)));
+        spread_expect(&mut helper, None);
+
+        place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        spread_expect(&mut helper, Some(dec!(2)));
+    }
+
+    #[test]
+    fn test_get_order() {
+        let mut helper = new_bidbook();
+        let order_id = place_bid_expect_receipt(&mut helper, 2, dec!(10));
+
+        let order = fetch_order(&mut helper, order_id.clone()).unwrap();
+        assert_eq!(order.order_id, order_id);
+        assert_eq!(order.order_qty, 2);
+        assert_eq!(order.order_price, dec!(10));
+        assert_eq!(order.order_total, dec!(20));
+
+        helper
+            .cancel_bid(order_id.resource_address())
+            .execute_expect_success(false);
+        assert!(fetch_order(&mut helper, order_id).is_none());
+    }
+
+    #[test]
+    fn test_get_orders_at_level_pages_in_queue_order() {
+        let mut helper = new_bidbook();
+        let first = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let second = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        let third = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        place_bid_expect_receipt(&mut helper, 1, dec!(20));
+
+        level_orders_expect(&mut helper, dec!(10), None, 2, vec![first, second.clone()]);
+        level_orders_expect(&mut helper, dec!(10), Some(second), 2, vec![third.clone()]);
+        level_orders_expect(&mut helper, dec!(10), Some(third), 2, vec![]);
+        level_orders_expect(&mut helper, dec!(15), None, 2, vec![]);
+    }
+
+    #[test]
+    fn test_get_orders_by_ids_keeps_unknown_ids() {
+        let mut helper = new_bidbook();
+        let filled = place_bid_expect_receipt(&mut helper, 1, dec!(20));
+        let open = place_bid_expect_receipt(&mut helper, 1, dec!(10));
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(20));
+
+        let receipt = helper
+            .get_orders_by_ids(vec![filled, open.clone()])
+            .execute_expect_success(false);
+        let orders = receipt.outputs::<Vec<Option<Order>>>("get_orders_by_ids")[0].clone();
+
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].is_none());
+        assert_eq!(orders[1].as_ref().unwrap().order_id, open);
+    }
+}
@@ -0,0 +1,42 @@
+mod helper;
+use helper::*;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bidbook_quoted_in(divisibility: u8) -> (HelloSwapTestHelper, ResourceAddress) {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        let quote_address = helper.create_quote_resource(divisibility);
+        helper.instantiate_quoted(collection, quote_address, Some(owner_badge), false);
+        (helper, owner_badge)
+    }
+
+    #[test]
+    fn test_fill_bid_quoted_in_six_decimals() {
+        let (mut helper, owner_badge) = bidbook_quoted_in(6);
+        helper
+            .set_order_limits(dec!(0.001), dec!(1), 1, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .set_fee_schedule(0, 33, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(1.001), 1, dec!(1.001))
+            .execute_expect_success(false);
+
+        // 33 bps of 1.001 is 0.0033033, rounded down to the 6 decimals the quote resource can hold
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(0.997697));
+    }
+
+    #[test]
+    fn test_tick_size_beyond_quote_divisibility() {
+        let (mut helper, owner_badge) = bidbook_quoted_in(6);
+        helper
+            .set_order_limits(dec!(0.0000001), dec!(1), 1, Some(owner_badge))
+            .execute_expect_failure(false);
+    }
+}
@@ -0,0 +1,187 @@
+mod helper;
+use helper::*;
+use radix_engine::system::system_modules::execution_trace::ResourceSpecifier::Amount;
+use scrypto::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bidbook_with_royalty_override(
+        royalty_percentage: Decimal,
+    ) -> (HelloSwapTestHelper, ResourceAddress) {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let royalty_badge = helper.x_address();
+        helper.instantiate_with_royalty(collection, None, royalty_percentage, royalty_badge, false);
+        (helper, royalty_badge)
+    }
+
+    #[test]
+    fn test_royalty_override_deducted_from_proceeds() {
+        let (mut helper, royalty_badge) = bidbook_with_royalty_override(dec!(5));
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(95));
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(5))]]);
+    }
+
+    #[test]
+    fn test_royalty_taken_after_taker_fee() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        let royalty_badge = helper.x_address();
+        helper.instantiate_with_royalty(
+            collection,
+            Some(owner_badge),
+            dec!(10),
+            royalty_badge,
+            false,
+        );
+        helper
+            .set_fee_schedule(0, 100, Some(owner_badge))
+            .execute_expect_success(false);
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+
+        // 1 of fee, then 10% of the remaining 99 as royalty
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(89.1));
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(9.9))]]);
+    }
+
+    #[test]
+    fn test_royalty_from_collection_metadata() {
+        let mut helper = HelloSwapTestHelper::new();
+        let owner_badge = helper.u_address();
+        let royalty_badge = helper.x_address();
+        let collection = helper.create_royalty_collection(owner_badge, dec!(10), royalty_badge);
+        helper.instantiate_default(collection, false);
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+
+        let receipt = helper
+            .fill_bid(collection, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("fill_bid");
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(90))]]);
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(10))]]);
+    }
+
+    fn claim_ask_expect_proceeds(
+        helper: &mut HelloSwapTestHelper,
+        receipt_address: ResourceAddress,
+        proceeds_expected: Decimal,
+    ) {
+        let receipt = helper
+            .claim_ask(receipt_address, dec!(1))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_ask");
+
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), proceeds_expected);
+    }
+
+    #[test]
+    fn test_royalty_deducted_when_ask_is_filled() {
+        let (mut helper, royalty_badge) = bidbook_with_royalty_override(dec!(5));
+        let order_id = place_ask_expect_receipt(&mut helper, 1, dec!(100));
+        helper.fill_ask(dec!(100), 1).execute_expect_success(false);
+
+        claim_ask_expect_proceeds(&mut helper, order_id.resource_address(), dec!(95));
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(5))]]);
+    }
+
+    #[test]
+    fn test_royalty_taken_after_taker_fee_when_ask_is_filled() {
+        let mut helper = HelloSwapTestHelper::new();
+        let collection = helper.j_nft_address();
+        let owner_badge = helper.u_address();
+        let royalty_badge = helper.x_address();
+        helper.instantiate_with_royalty(
+            collection,
+            Some(owner_badge),
+            dec!(10),
+            royalty_badge,
+            false,
+        );
+        helper
+            .set_fee_schedule(0, 100, Some(owner_badge))
+            .execute_expect_success(false);
+        let order_id = place_ask_expect_receipt(&mut helper, 1, dec!(100));
+
+        // the buyer pays the listed price
+        let receipt = helper.fill_ask(dec!(100), 1).execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("fill_ask");
+        assert_eq!(bucket_amount(&output_buckets[0], XRD), dec!(0));
+
+        claim_ask_expect_proceeds(&mut helper, order_id.resource_address(), dec!(89.1));
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(9.9))]]);
+
+        let receipt = helper
+            .admin_call("withdraw_fees", Some(owner_badge))
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("withdraw_fees");
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(1))]]);
+    }
+
+    #[test]
+    fn test_royalty_deducted_when_bid_crosses_ask() {
+        let (mut helper, royalty_badge) = bidbook_with_royalty_override(dec!(5));
+        let order_id = place_ask_expect_receipt(&mut helper, 1, dec!(100));
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+
+        claim_ask_expect_proceeds(&mut helper, order_id.resource_address(), dec!(95));
+
+        let receipt = helper
+            .claim_royalties(royalty_badge)
+            .execute_expect_success(false);
+        let output_buckets = receipt.output_buckets("claim_royalties");
+
+        assert_eq!(output_buckets, vec![vec![Amount(XRD, dec!(5))]]);
+    }
+
+    #[test]
+    fn test_claim_royalties_without_badge() {
+        let (mut helper, _royalty_badge) = bidbook_with_royalty_override(dec!(5));
+        helper
+            .place_bid(dec!(100), 1, dec!(100))
+            .execute_expect_success(false);
+        fill_bid_expect_payout(&mut helper, dec!(1), dec!(95));
+
+        helper.claim_royalties(XRD).execute_expect_failure(false);
+    }
+}